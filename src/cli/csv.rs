@@ -1,26 +1,480 @@
 use crate::{verify_input, CmdExector};
 use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Parser, Clone)]
+#[derive(Debug, Parser, Clone, Copy)]
 pub enum OutputFormat {
     Raw,
+    Csv,
     Json,
+    Jsonl,
     Yaml,
     Toml,
+    Markdown,
+    Html,
+    Xml,
+    Parquet,
+    Arrow,
+    Sql,
+    Sqlite,
+    Xlsx,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum InputFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Distinct,
+}
+
+/// How `csv split` assigns rows to files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitBy {
+    Rows(usize),
+    Column(String),
+}
+
+/// How `csv mask` replaces the cells of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskMethod {
+    Hash,
+    Fake,
+    Redact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Sqlite,
+    Postgres,
+    Mysql,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Date,
 }
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CsvOpts {
-    #[arg(short, long, value_parser = verify_input)]
-    pub input: String,
+    #[command(subcommand)]
+    pub cmd: Option<Box<CsvSubcommand>>,
+    #[arg(short, long, value_parser = verify_input, required = true)]
+    pub input: Option<String>,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        help = "convert row by row with flat memory use: json becomes JSON Lines, yaml a document stream"
+    )]
+    pub stream: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        help = "infer integer, float, boolean and date columns, and turn empty cells into null"
+    )]
+    pub infer_types: bool,
+    #[arg(
+        long,
+        value_parser = parse_column_type,
+        help = "force a column type as COLUMN=TYPE, with TYPE one of string, integer, float, boolean, date"
+    )]
+    pub column_type: Vec<(String, CsvType)>,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        help = "build nested objects and arrays from headers like address.city and tags[0]"
+    )]
+    pub nested: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "columns to keep, in output order, by name or by zero-based index with --no-header"
+    )]
+    pub select: Vec<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "columns to drop, by name or by zero-based index with --no-header"
+    )]
+    pub exclude: Vec<String>,
+    #[arg(long, value_parser = parse_rename, help = "rename a column as OLD=NEW")]
+    pub rename: Vec<(String, String)>,
+    #[arg(
+        long = "where",
+        help = r#"only keep rows matching an expression like `Position == "Goalkeeper" and Age > 30`"#
+    )]
+    pub filter: Option<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_sort_key,
+        help = "sort by columns, numbers by value, as COLUMN[:desc]"
+    )]
+    pub sort_by: Vec<crate::SortKey>,
+    #[arg(
+        long,
+        num_args = 0..,
+        value_delimiter = ',',
+        help = "drop repeated rows, comparing only the given columns if any"
+    )]
+    pub dedup: Option<Vec<String>>,
+    #[arg(
+        long,
+        default_value_t = 100_000,
+        help = "rows to sort or deduplicate in memory before spilling to temp files"
+    )]
+    pub sort_buffer: usize,
+    #[arg(long, default_value_t = 0, help = "skip this many rows")]
+    pub offset: usize,
+    #[arg(long, help = "keep at most this many rows, after --offset")]
+    pub limit: Option<usize>,
+    #[arg(long, help = "keep only the last N rows")]
+    pub tail: Option<usize>,
+    #[arg(
+        long,
+        help = "keep a random sample of N rows, in one pass over the input"
+    )]
+    pub sample: Option<usize>,
+    #[arg(long, requires = "sample", help = "seed for a reproducible --sample")]
+    pub seed: Option<u64>,
+}
+
+/// How to parse the CSV input, shared by every command that reads CSV.
+#[derive(Debug, Parser)]
+pub struct CsvReadOpts {
+    #[arg(short, long, help = "input delimiter [default: detected]")]
+    pub delimiter: Option<char>,
+    #[arg(long, help = "input quote character [default: detected]")]
+    pub quote: Option<char>,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with = "no_header",
+        help = "the first row is a header [default: detected]"
+    )]
+    pub header: bool,
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub no_header: bool,
+    #[arg(
+        long,
+        help = "input encoding such as windows-1252 or gbk [default: detected]"
+    )]
+    pub encoding: Option<String>,
+    #[arg(
+        long,
+        help = "sheet of an xlsx, xls or ods input, by name or 1-based position [default: the first]"
+    )]
+    pub sheet: Option<String>,
+}
+
+/// Where and how to write the output, shared by every command with a --format.
+#[derive(Debug, Parser)]
+pub struct CsvWriteOpts {
     #[arg(short, long, default_value = "-")]
     pub output: String,
-    #[arg(short, long, default_value = "raw")]
+    #[arg(
+        short,
+        long,
+        default_value = "raw",
+        help = "raw (an aligned table), csv (RFC 4180, CRLF line endings), json, jsonl, yaml, toml, markdown, html, xml, parquet, arrow, sql, sqlite (rows written into the --output database) or xlsx"
+    )]
     pub format: OutputFormat,
+    #[arg(
+        long,
+        default_value_t = 40,
+        help = "cut raw table cells wider than this many columns, 0 for no limit"
+    )]
+    pub max_width: usize,
+    #[arg(
+        long,
+        default_value_t = 65_536,
+        help = "rows per Parquet row group or Arrow record batch"
+    )]
+    pub row_group_size: usize,
+    #[arg(
+        long,
+        default_value = "sqlite",
+        help = "SQL flavour of --format sql: sqlite, postgres or mysql"
+    )]
+    pub sql_dialect: SqlDialect,
+    #[arg(
+        long,
+        help = "table for sql and sqlite output, replaced if it exists [default: the input file name]"
+    )]
+    pub table: Option<String>,
+    #[arg(long, default_value_t = 500, help = "rows per SQL INSERT statement")]
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum CsvSubcommand {
+    #[command(name = "from", about = "Convert JSON, YAML or TOML into CSV")]
+    From(CsvFromOpts),
+    #[command(name = "schema", about = "Infer a JSON schema of the CSV columns")]
+    Schema(CsvSchemaOpts),
+    #[command(
+        name = "validate",
+        about = "Validate a CSV file against a saved schema"
+    )]
+    Validate(CsvValidateOpts),
+    #[command(name = "stats", about = "Show per-column statistics of a CSV file")]
+    Stats(CsvStatsOpts),
+    #[command(name = "join", about = "Join two CSV files on key columns")]
+    Join(CsvJoinOpts),
+    #[command(name = "groupby", about = "Aggregate rows grouped by key columns")]
+    GroupBy(CsvGroupByOpts),
+    #[command(name = "query", about = "Run a SQL query against CSV files")]
+    Query(CsvQueryOpts),
+    #[command(
+        name = "diff",
+        about = "Show rows added, removed and changed between two CSV files"
+    )]
+    Diff(CsvDiffOpts),
+    #[command(
+        name = "split",
+        about = "Split a CSV file into parts by row count or column value"
+    )]
+    Split(CsvSplitOpts),
+    #[command(name = "cat", about = "Concatenate CSV files, lining up their columns")]
+    Cat(CsvCatOpts),
+    #[command(name = "mask", about = "Hash, fake or redact sensitive CSV columns")]
+    Mask(CsvMaskOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvFromOpts {
+    #[arg(short, long, value_parser = verify_input, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(
+        short,
+        long,
+        help = "input format: json, yaml or toml [default: detected from the input extension]"
+    )]
+    pub format: Option<InputFormat>,
     #[arg(short, long, default_value_t = ',')]
     pub delimiter: char,
-    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
-    pub no_header: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        help = "flatten nested objects and arrays into address.city and tags[0] columns"
+    )]
+    pub flatten: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSchemaOpts {
+    #[arg(short, long, value_parser = verify_input)]
+    pub input: String,
+    #[arg(short, long, help = "schema file", default_value = "-")]
+    pub output: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvValidateOpts {
+    #[arg(short, long, value_parser = verify_input)]
+    pub input: String,
+    #[arg(short, long, value_parser = verify_input, help = "schema written by `csv schema`")]
+    pub schema: String,
+    #[arg(short, long, help = "validation report", default_value = "-")]
+    pub output: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvStatsOpts {
+    #[arg(short, long, value_parser = verify_input)]
+    pub input: String,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "most frequent values to list for text columns"
+    )]
+    pub top: usize,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvJoinOpts {
+    #[arg(value_parser = verify_input)]
+    pub left: String,
+    #[arg(value_parser = verify_input)]
+    pub right: String,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        value_parser = parse_join_key,
+        help = "key columns, as COLUMN or LEFT=RIGHT when the names differ"
+    )]
+    pub on: Vec<crate::JoinKey>,
+    #[arg(
+        long,
+        default_value = "inner",
+        help = "join kind: inner, left, right or full"
+    )]
+    pub how: JoinKind,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvGroupByOpts {
+    #[arg(short, long, value_parser = verify_input)]
+    pub input: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "columns to group by, all rows form one group without it"
+    )]
+    pub by: Vec<String>,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        value_parser = parse_aggregate,
+        help = "aggregates like count,avg(Age),max(Kit Number); count, sum, avg, min, max, distinct"
+    )]
+    pub agg: Vec<crate::Aggregate>,
+    #[arg(
+        long = "where",
+        help = "only aggregate rows matching an expression, as in `csv --where`"
+    )]
+    pub filter: Option<String>,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvQueryOpts {
+    #[arg(
+        help = "SQLite SQL, each input is a table named after its file and the first is also `t`"
+    )]
+    pub sql: String,
+    #[arg(
+        short,
+        long = "input",
+        value_parser = verify_input,
+        required = true,
+        help = "CSV files to query, repeat for more tables"
+    )]
+    pub inputs: Vec<String>,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDiffOpts {
+    #[arg(value_parser = verify_input)]
+    pub old: String,
+    #[arg(value_parser = verify_input)]
+    pub new: String,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        help = "columns identifying a row in both files"
+    )]
+    pub key: Vec<String>,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+#[command(group(clap::ArgGroup::new("split_by").required(true).args(["rows", "by"])))]
+pub struct CsvSplitOpts {
+    #[arg(short, long, value_parser = verify_input)]
+    pub input: String,
+    #[arg(long, help = "rows per part")]
+    pub rows: Option<usize>,
+    #[arg(long, help = "one part per distinct value of this column")]
+    pub by: Option<String>,
+    #[arg(long, default_value = ".", help = "directory to write the parts to")]
+    pub dir: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvCatOpts {
+    #[arg(value_parser = verify_input, required = true)]
+    pub inputs: Vec<String>,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvMaskOpts {
+    #[arg(short, long, value_parser = verify_input, default_value = "-")]
+    pub input: String,
+    #[arg(
+        long,
+        required = true,
+        value_parser = parse_mask,
+        help = "column to mask as COLUMN:METHOD, METHOD is hash, fake or redact"
+    )]
+    pub column: Vec<crate::ColumnMask>,
+    #[arg(
+        short,
+        long,
+        help = "BLAKE3 key to hash with, also makes fake values the same across runs"
+    )]
+    pub key: Option<String>,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
 }
 
 impl std::str::FromStr for OutputFormat {
@@ -28,23 +482,405 @@ impl std::str::FromStr for OutputFormat {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "raw" => Ok(OutputFormat::Raw),
+            "raw" | "table" => Ok(OutputFormat::Raw),
+            "csv" => Ok(OutputFormat::Csv),
             "json" => Ok(OutputFormat::Json),
+            "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
             "yaml" => Ok(OutputFormat::Yaml),
             "toml" => Ok(OutputFormat::Toml),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            "xml" => Ok(OutputFormat::Xml),
+            "parquet" => Ok(OutputFormat::Parquet),
+            "arrow" | "ipc" | "feather" => Ok(OutputFormat::Arrow),
+            "sql" => Ok(OutputFormat::Sql),
+            "sqlite" | "sqlite3" | "db" => Ok(OutputFormat::Sqlite),
+            "xlsx" | "excel" => Ok(OutputFormat::Xlsx),
             _ => Err(anyhow::anyhow!("Invalid CSV output format")),
         }
     }
 }
 
+impl std::str::FromStr for InputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(InputFormat::Json),
+            "yaml" | "yml" => Ok(InputFormat::Yaml),
+            "toml" => Ok(InputFormat::Toml),
+            _ => Err(anyhow::anyhow!("Invalid CSV input format")),
+        }
+    }
+}
+
+impl std::str::FromStr for JoinKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "inner" => Ok(JoinKind::Inner),
+            "left" => Ok(JoinKind::Left),
+            "right" => Ok(JoinKind::Right),
+            "full" | "outer" | "full-outer" => Ok(JoinKind::Full),
+            _ => Err(anyhow::anyhow!("Invalid join kind")),
+        }
+    }
+}
+
+impl std::str::FromStr for AggFunc {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "count" => Ok(AggFunc::Count),
+            "sum" => Ok(AggFunc::Sum),
+            "avg" | "mean" => Ok(AggFunc::Avg),
+            "min" => Ok(AggFunc::Min),
+            "max" => Ok(AggFunc::Max),
+            "distinct" => Ok(AggFunc::Distinct),
+            _ => Err(anyhow::anyhow!("Invalid aggregate function")),
+        }
+    }
+}
+
+impl std::str::FromStr for SqlDialect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sqlite" | "sqlite3" => Ok(SqlDialect::Sqlite),
+            "postgres" | "postgresql" | "pg" => Ok(SqlDialect::Postgres),
+            "mysql" | "mariadb" => Ok(SqlDialect::Mysql),
+            _ => Err(anyhow::anyhow!("Invalid SQL dialect")),
+        }
+    }
+}
+
+impl std::str::FromStr for MaskMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hash" => Ok(MaskMethod::Hash),
+            "fake" => Ok(MaskMethod::Fake),
+            "redact" => Ok(MaskMethod::Redact),
+            _ => Err(anyhow::anyhow!("Invalid mask method")),
+        }
+    }
+}
+
+impl AggFunc {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AggFunc::Count => "count",
+            AggFunc::Sum => "sum",
+            AggFunc::Avg => "avg",
+            AggFunc::Min => "min",
+            AggFunc::Max => "max",
+            AggFunc::Distinct => "distinct",
+        }
+    }
+}
+
+impl std::str::FromStr for CsvType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "string" | "str" => Ok(CsvType::String),
+            "integer" | "int" => Ok(CsvType::Integer),
+            "float" => Ok(CsvType::Float),
+            "boolean" | "bool" => Ok(CsvType::Boolean),
+            "date" => Ok(CsvType::Date),
+            _ => Err(anyhow::anyhow!("Invalid CSV column type")),
+        }
+    }
+}
+
+fn parse_column_type(s: &str) -> anyhow::Result<(String, CsvType)> {
+    let (column, ty) = s
+        .rsplit_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected COLUMN=TYPE"))?;
+    Ok((column.to_string(), ty.parse()?))
+}
+
+fn parse_rename(s: &str) -> anyhow::Result<(String, String)> {
+    let (old, new) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected OLD=NEW"))?;
+    Ok((old.to_string(), new.to_string()))
+}
+
+impl CsvReadOpts {
+    fn config(&self) -> crate::CsvConfig {
+        crate::CsvConfig {
+            delimiter: self.delimiter,
+            quote: self.quote,
+            headers: match (self.header, self.no_header) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            encoding: self.encoding.clone(),
+            sheet: self.sheet.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl CsvWriteOpts {
+    fn config(&self, read: &CsvReadOpts) -> crate::CsvConfig {
+        crate::CsvConfig {
+            max_width: self.max_width,
+            row_group_size: self.row_group_size,
+            sql_dialect: self.sql_dialect,
+            table: self.table.clone(),
+            batch_size: self.batch_size,
+            ..read.config()
+        }
+    }
+}
+
+fn parse_sort_key(s: &str) -> anyhow::Result<crate::SortKey> {
+    let (column, descending) = match s.rsplit_once(':') {
+        Some((column, order)) if order.eq_ignore_ascii_case("desc") => (column, true),
+        Some((column, order)) if order.eq_ignore_ascii_case("asc") => (column, false),
+        _ => (s, false),
+    };
+    Ok(crate::SortKey {
+        column: column.to_string(),
+        descending,
+    })
+}
+
+fn parse_join_key(s: &str) -> anyhow::Result<crate::JoinKey> {
+    let (left, right) = s.split_once('=').unwrap_or((s, s));
+    Ok(crate::JoinKey {
+        left: left.to_string(),
+        right: right.to_string(),
+    })
+}
+
+fn parse_mask(s: &str) -> anyhow::Result<crate::ColumnMask> {
+    let (column, method) = s
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Expected COLUMN:METHOD, like Email:hash"))?;
+    Ok(crate::ColumnMask {
+        column: column.to_string(),
+        method: method.parse()?,
+    })
+}
+
+fn parse_aggregate(s: &str) -> anyhow::Result<crate::Aggregate> {
+    let s = s.trim();
+    let (func, column) = match s.split_once('(') {
+        Some((func, rest)) => {
+            let column = rest
+                .strip_suffix(')')
+                .ok_or_else(|| anyhow::anyhow!("Expected FUNC(COLUMN), missing `)`"))?;
+            (func, Some(column.to_string()))
+        }
+        None => (s, None),
+    };
+    Ok(crate::Aggregate {
+        func: func.trim().parse()?,
+        column,
+    })
+}
+
+impl InputFormat {
+    /// The format from the input extension, looking past a compression one like `.gz`.
+    fn detect(input: &str) -> anyhow::Result<Self> {
+        let mut path = std::path::PathBuf::from(input);
+        if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("gz" | "zst" | "bz2" | "xz")
+        ) {
+            path.set_extension("");
+        }
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Can't detect the input format, pass --format"))?
+            .parse()
+    }
+}
+
 impl CmdExector for CsvOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        crate::process_csv(
+        if let Some(cmd) = self.cmd {
+            return (*cmd).execute().await;
+        }
+
+        let input = self
+            .input
+            .expect("clap requires --input without a subcommand");
+        let config = crate::CsvConfig {
+            stream: self.stream,
+            types: crate::ColumnTypes {
+                infer: self.infer_types,
+                columns: self.column_type,
+                nested: self.nested,
+            },
+            columns: crate::ColumnSelection {
+                select: self.select,
+                exclude: self.exclude,
+                rename: self.rename,
+            },
+            filter: self.filter,
+            sort: self.sort_by,
+            dedup: self.dedup,
+            sort_buffer: self.sort_buffer,
+            offset: self.offset,
+            limit: self.limit,
+            tail: self.tail,
+            sample: self.sample,
+            seed: self.seed,
+            ..self.write.config(&self.read)
+        };
+        crate::process_csv(&input, &self.write.output, self.write.format, &config)?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvFromOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let format = match self.format {
+            Some(format) => format,
+            None => InputFormat::detect(&self.input)?,
+        };
+        crate::process_csv_from(
             &self.input,
             &self.output,
-            self.format,
+            format,
             self.delimiter,
-            self.no_header,
+            self.flatten,
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvSchemaOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_schema(&self.input, &self.output, &self.read.config())?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvValidateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_validate(&self.input, &self.schema, &self.output, &self.read.config())?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvStatsOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_stats(
+            &self.input,
+            &self.write.output,
+            self.write.format,
+            self.top,
+            &self.write.config(&self.read),
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvJoinOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_join(
+            &self.left,
+            &self.right,
+            &self.write.output,
+            self.write.format,
+            &self.on,
+            self.how,
+            &self.write.config(&self.read),
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvGroupByOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let config = crate::CsvConfig {
+            filter: self.filter,
+            ..self.write.config(&self.read)
+        };
+        crate::process_csv_groupby(
+            &self.input,
+            &self.write.output,
+            self.write.format,
+            &self.by,
+            &self.agg,
+            &config,
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvQueryOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_query(
+            &self.inputs,
+            &self.write.output,
+            self.write.format,
+            &self.sql,
+            &self.write.config(&self.read),
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvDiffOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_diff(
+            &self.old,
+            &self.new,
+            &self.write.output,
+            self.write.format,
+            &self.key,
+            &self.write.config(&self.read),
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvSplitOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let by = match (self.rows, self.by) {
+            (Some(rows), _) => SplitBy::Rows(rows),
+            (None, Some(column)) => SplitBy::Column(column),
+            (None, None) => unreachable!("clap requires --rows or --by"),
+        };
+        crate::process_csv_split(&self.input, &self.dir, &by, &self.read.config())?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvCatOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_cat(
+            &self.inputs,
+            &self.write.output,
+            self.write.format,
+            &self.write.config(&self.read),
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExector for CsvMaskOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = self.key.as_deref().map(crate::get_content).transpose()?;
+        crate::process_csv_mask(
+            &self.input,
+            &self.write.output,
+            self.write.format,
+            &self.column,
+            key.as_deref(),
+            &self.write.config(&self.read),
         )?;
         Ok(())
     }
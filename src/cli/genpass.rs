@@ -14,17 +14,74 @@ pub struct GenpassOpts {
     pub no_num: bool,
     #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub no_symbol: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["no_upper", "no_lower", "no_num", "no_symbol", "symbols"],
+        help = "custom alphabet to draw every character from, like 0123456789abcdef"
+    )]
+    pub charset: Option<String>,
+    #[arg(long, default_value = "", help = "characters never to use")]
+    pub exclude: String,
+    #[arg(long, help = "symbols to use instead of !@#$%^&*-_")]
+    pub symbols: Option<String>,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        help = "also use look-alike characters: 0, O, I and l"
+    )]
+    pub ambiguous: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["length", "no_upper", "no_lower", "no_num", "no_symbol", "charset", "exclude", "symbols", "ambiguous"],
+        help = "generate a passphrase of this many words instead"
+    )]
+    pub words: Option<u8>,
+    #[arg(
+        long,
+        default_value = "-",
+        requires = "words",
+        help = "separator between passphrase words"
+    )]
+    pub separator: String,
+    #[arg(
+        long,
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        requires = "words",
+        help = "capitalize passphrase words"
+    )]
+    pub capitalize: bool,
+    #[arg(
+        long,
+        default_value = "bip39",
+        requires = "words",
+        help = "bip39 for the built-in list, or a wordlist file such as EFF's eff_large_wordlist.txt"
+    )]
+    pub wordlist: String,
 }
 
 impl CmdExector for GenpassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let password = crate::process_genpass(
-            self.length,
-            self.no_upper,
-            self.no_lower,
-            self.no_num,
-            self.no_symbol,
-        )?;
+        let password = match self.words {
+            Some(words) => crate::process_passphrase(&crate::PassphraseConfig {
+                words,
+                separator: self.separator,
+                capitalize: self.capitalize,
+                wordlist: self.wordlist,
+            })?,
+            None => crate::process_genpass(&crate::GenpassConfig {
+                length: self.length,
+                upper: !self.no_upper,
+                lower: !self.no_lower,
+                number: !self.no_num,
+                symbol: !self.no_symbol,
+                symbols: self.symbols,
+                charset: self.charset,
+                exclude: self.exclude,
+                ambiguous: self.ambiguous,
+            })?,
+        };
 
         println!("{}", password);
         let estimate = zxcvbn::zxcvbn(&password, &[])?;
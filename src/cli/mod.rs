@@ -25,8 +25,11 @@ pub struct Opts {
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExector)]
 pub enum SubCommand {
-    #[command(name = "csv", about = "Show CSV, or convert CSV to other formats")]
-    Csv(CsvOpts),
+    #[command(
+        name = "csv",
+        about = "Show CSV, convert CSV to other formats, or convert other formats to CSV"
+    )]
+    Csv(Box<CsvOpts>),
     #[command(name = "genpass", about = "Generate a random password")]
     Genpass(GenpassOpts),
     #[command(subcommand, about = "Encode or decode base64")]
@@ -44,3 +47,9 @@ pub enum SubCommand {
 pub trait CmdExector {
     async fn execute(self) -> Result<()>;
 }
+
+impl<T: CmdExector> CmdExector for Box<T> {
+    async fn execute(self) -> Result<()> {
+        (*self).execute().await
+    }
+}
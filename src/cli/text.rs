@@ -110,7 +110,7 @@ impl CmdExector for VerifyOpts {
 impl CmdExector for GenerateOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let key = crate::process_generate(self.format);
-        crate::output_contents(&self.output, &key);
+        crate::output_contents(&self.output, &key)?;
         Ok(())
     }
 }
@@ -120,7 +120,7 @@ impl CmdExector for EncryptOpts {
         let mut reader = crate::get_reader(&self.input)?;
         let key = crate::get_content(&self.key)?;
         let encrypted = crate::process_encrypt(&mut reader, &key)?;
-        crate::output_contents(&self.output, &encrypted);
+        crate::output_contents(&self.output, &encrypted)?;
         Ok(())
     }
 }
@@ -130,7 +130,7 @@ impl CmdExector for DecryptOpts {
         let mut reader = crate::get_reader(&self.input)?;
         let key = crate::get_content(&self.key)?;
         let decrypted = crate::process_decrypt(&mut reader, &key)?;
-        crate::output_contents(&self.output, &decrypted);
+        crate::output_contents(&self.output, &decrypted)?;
         Ok(())
     }
 }
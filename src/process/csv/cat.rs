@@ -0,0 +1,107 @@
+use super::{csv_reader, read_headers, read_rows, CsvConfig, RowWriter, ValueConverter};
+use crate::{get_writer, OutputFormat};
+use csv::StringRecord;
+use std::io::Write;
+
+/// Write the rows of every input one file after another, streaming.
+///
+/// The header is the union of all input headers in first-seen order, and each row is
+/// laid out under it with empty cells for the columns its file doesn't have.
+fn csv_cat<W: Write>(
+    inputs: &[String],
+    writer: W,
+    format: OutputFormat,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let mut readers = Vec::new();
+    for input in inputs {
+        let mut rdr = csv_reader(input, config)?;
+        let headers = read_headers(&mut rdr)?;
+        readers.push((rdr, headers));
+    }
+    let with_header = readers.iter().filter(|(_, h)| h.is_some()).count();
+    let headers = match with_header {
+        0 => None,
+        n if n == readers.len() => Some(union_headers(readers.iter().flat_map(|(_, h)| h))),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Only some inputs have a header row, pass --header or --no-header"
+            ))
+        }
+    };
+
+    let converter = ValueConverter::for_stream(&config.types, headers.as_ref())?;
+    let mut wtr = RowWriter::new(writer, format, config.output_delimiter(), headers.as_ref())?;
+    for (rdr, file_headers) in readers {
+        // where each output column is in this file, None without headers
+        let layout: Option<Vec<Option<usize>>> =
+            headers
+                .as_ref()
+                .zip(file_headers.as_ref())
+                .map(|(headers, file_headers)| {
+                    headers
+                        .iter()
+                        .map(|column| file_headers.iter().position(|c| c == column))
+                        .collect()
+                });
+        for record in read_rows(rdr, file_headers.as_ref(), config)? {
+            let record = record?;
+            let record = match &layout {
+                Some(layout) => layout
+                    .iter()
+                    .map(|i| i.and_then(|i| record.get(i)).unwrap_or_default())
+                    .collect(),
+                None => record,
+            };
+            wtr.write(&converter, headers.as_ref(), &record)?;
+        }
+    }
+    wtr.finish()
+}
+
+/// Header of all inputs: the columns of each in first-seen order.
+fn union_headers<'a>(headers: impl Iterator<Item = &'a StringRecord>) -> StringRecord {
+    let mut union: Vec<&str> = Vec::new();
+    for column in headers.flatten() {
+        if !union.contains(&column) {
+            union.push(column);
+        }
+    }
+    union.into()
+}
+
+pub fn process_csv_cat(
+    inputs: &[String],
+    output: &str,
+    format: OutputFormat,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let mut writer = get_writer(output)?;
+    csv_cat(inputs, &mut writer, format, config)?;
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cat_reconciles_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let inputs = [
+            path("a.csv", "name,kit\nPerin,37\n"),
+            path("b.csv", "country,name\nItaly,Buffon\n"),
+        ];
+        let mut buf = Vec::new();
+        csv_cat(&inputs, &mut buf, OutputFormat::Csv, &CsvConfig::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "name,kit,country\r\nPerin,37,\r\nBuffon,,Italy\r\n"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use super::{record_types, CsvConfig, CsvRecord};
+use crate::{CsvType, OutputFormat};
+use arrow_array::{
+    builder::{BooleanBuilder, Date32Builder, Float64Builder, Int64Builder, StringBuilder},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use serde_json::Value;
+use std::{io::Write, sync::Arc};
+
+/// Write Parquet or an Arrow IPC file.
+///
+/// Column types are always inferred, `--column-type` still overrides them, and empty
+/// cells of typed columns become nulls. Rows are written in batches of `row_group_size`,
+/// each batch a Parquet row group or an IPC record batch.
+pub(super) fn write_columnar<W: Write + Send>(
+    csv_record: CsvRecord,
+    format: OutputFormat,
+    writer: W,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let types = record_types(&csv_record, &config.types)?;
+    let schema = arrow_schema(&csv_record, &types);
+    let batches = csv_record
+        .records
+        .chunks(config.row_group_size.max(1))
+        .map(|rows| record_batch(rows, &types, schema.clone()));
+
+    match format {
+        OutputFormat::Parquet => {
+            let props = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .set_max_row_group_size(config.row_group_size.max(1))
+                .build();
+            let mut wtr = ArrowWriter::try_new(writer, schema.clone(), Some(props))?;
+            for batch in batches {
+                wtr.write(&batch?)?;
+                // one row group per batch, even when the size limit isn't reached
+                wtr.flush()?;
+            }
+            wtr.close()?;
+        }
+        OutputFormat::Arrow => {
+            let mut wtr = arrow_ipc::writer::FileWriter::try_new(writer, &schema)?;
+            for batch in batches {
+                wtr.write(&batch?)?;
+            }
+            wtr.finish()?;
+        }
+        format => unreachable!("{:?} is not a columnar format", format),
+    }
+    Ok(())
+}
+
+fn arrow_schema(csv_record: &CsvRecord, types: &[CsvType]) -> SchemaRef {
+    let fields: Vec<Field> = types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let name = csv_record
+                .headers
+                .as_ref()
+                .and_then(|h| h.get(i))
+                .map_or_else(|| i.to_string(), str::to_string);
+            let data_type = match ty {
+                CsvType::String => DataType::Utf8,
+                CsvType::Integer => DataType::Int64,
+                CsvType::Float => DataType::Float64,
+                CsvType::Boolean => DataType::Boolean,
+                CsvType::Date => DataType::Date32,
+            };
+            Field::new(name, data_type, true)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+fn record_batch(
+    rows: &[csv::StringRecord],
+    types: &[CsvType],
+    schema: SchemaRef,
+) -> anyhow::Result<RecordBatch> {
+    let columns = types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let values = rows.iter().map(|row| {
+                let cell = row.get(i).unwrap_or_default();
+                ty.convert(cell).map_err(|e| {
+                    let column = schema.field(i).name();
+                    let line = row.position().map_or(0, |p| p.line());
+                    anyhow::anyhow!("{} in column `{}` on line {}", e, column, line)
+                })
+            });
+            column_array(*ty, values)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+fn column_array(
+    ty: CsvType,
+    values: impl Iterator<Item = anyhow::Result<Value>>,
+) -> anyhow::Result<ArrayRef> {
+    let array: ArrayRef = match ty {
+        CsvType::String => {
+            let mut builder = StringBuilder::new();
+            for value in values {
+                builder.append_option(value?.as_str());
+            }
+            Arc::new(builder.finish())
+        }
+        CsvType::Integer => {
+            let mut builder = Int64Builder::new();
+            for value in values {
+                builder.append_option(value?.as_i64());
+            }
+            Arc::new(builder.finish())
+        }
+        CsvType::Float => {
+            let mut builder = Float64Builder::new();
+            for value in values {
+                builder.append_option(value?.as_f64());
+            }
+            Arc::new(builder.finish())
+        }
+        CsvType::Boolean => {
+            let mut builder = BooleanBuilder::new();
+            for value in values {
+                builder.append_option(value?.as_bool());
+            }
+            Arc::new(builder.finish())
+        }
+        CsvType::Date => {
+            let mut builder = Date32Builder::new();
+            for value in values {
+                builder.append_option(value?.as_str().map(days_since_epoch));
+            }
+            Arc::new(builder.finish())
+        }
+    };
+    Ok(array)
+}
+
+/// Days between 1970-01-01 and an already validated YYYY-MM-DD date.
+fn days_since_epoch(date: &str) -> i32 {
+    let part = |range: std::ops::Range<usize>| date[range].parse::<i32>().unwrap_or_default();
+    let (year, month, day) = (part(0..4), part(5..7), part(8..10));
+    // shift the year to start in March so the leap day comes last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnTypes;
+    use arrow_array::{Array, Date32Array, Int64Array};
+    use csv::StringRecord;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::io::{Seek, SeekFrom};
+
+    fn players() -> CsvRecord {
+        CsvRecord {
+            headers: Some(StringRecord::from(vec!["Name", "Kit Number", "Joined"])),
+            records: vec![
+                StringRecord::from(vec!["Perin", "37", "2018-07-01"]),
+                StringRecord::from(vec!["Buffon", "", "2019-07-04"]),
+                StringRecord::from(vec!["Szczesny", "1", ""]),
+            ],
+        }
+    }
+
+    fn config(row_group_size: usize) -> CsvConfig {
+        CsvConfig {
+            row_group_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parquet_schema_and_row_groups() {
+        let mut file = tempfile::tempfile().unwrap();
+        write_columnar(players(), OutputFormat::Parquet, &mut file, &config(2)).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(builder.metadata().num_row_groups(), 2);
+        let schema = builder.schema().clone();
+        assert_eq!(schema.field(1).data_type(), &DataType::Int64);
+        assert_eq!(schema.field(2).data_type(), &DataType::Date32);
+
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        let kits = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(kits.value(0), 37);
+        assert!(kits.is_null(1));
+    }
+
+    #[test]
+    fn test_arrow_ipc_roundtrip() {
+        let mut buf = Vec::new();
+        write_columnar(players(), OutputFormat::Arrow, &mut buf, &config(10)).unwrap();
+        let reader =
+            arrow_ipc::reader::FileReader::try_new(std::io::Cursor::new(buf), None).unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 1);
+        let joined = batches[0]
+            .column(2)
+            .as_any()
+            .downcast_ref::<Date32Array>()
+            .unwrap();
+        assert_eq!(joined.value(0), days_since_epoch("2018-07-01"));
+        assert!(joined.is_null(2));
+    }
+
+    #[test]
+    fn test_days_since_epoch() {
+        assert_eq!(days_since_epoch("1970-01-01"), 0);
+        assert_eq!(days_since_epoch("2000-03-01"), 11_017);
+        assert_eq!(days_since_epoch("1969-12-31"), -1);
+    }
+
+    #[test]
+    fn test_forced_type_error_names_line() {
+        let mut csv_record = players();
+        csv_record.records[1] = StringRecord::from(vec!["Buffon", "x", ""]);
+        let config = CsvConfig {
+            types: ColumnTypes {
+                infer: false,
+                columns: vec![("Kit Number".to_string(), CsvType::Integer)],
+                nested: false,
+            },
+            ..Default::default()
+        };
+        let err = write_columnar(csv_record, OutputFormat::Arrow, Vec::new(), &config).unwrap_err();
+        assert!(err.to_string().contains("in column `Kit Number`"));
+    }
+}
@@ -0,0 +1,150 @@
+use super::{column_index, CsvRecord};
+use csv::StringRecord;
+
+/// Which columns to keep, in what order, and under which names.
+#[derive(Debug, Default, Clone)]
+pub struct ColumnSelection {
+    /// Columns to keep, in output order. Empty keeps every column.
+    pub select: Vec<String>,
+    /// Columns to drop.
+    pub exclude: Vec<String>,
+    /// Pairs of old and new header names.
+    pub rename: Vec<(String, String)>,
+}
+
+/// A column selection resolved against the input headers.
+#[derive(Debug)]
+pub(super) struct Projection {
+    indices: Option<Vec<usize>>,
+    exclude: Vec<usize>,
+    headers: Option<StringRecord>,
+}
+
+impl Projection {
+    pub(super) fn new(
+        selection: &ColumnSelection,
+        headers: Option<&StringRecord>,
+    ) -> anyhow::Result<Self> {
+        let resolve = |columns: &[String]| {
+            columns
+                .iter()
+                .map(|column| column_index(headers, column))
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        let exclude = resolve(&selection.exclude)?;
+        let mut indices = match (selection.select.is_empty(), headers) {
+            (false, _) => Some(resolve(&selection.select)?),
+            (true, Some(headers)) if !exclude.is_empty() => Some((0..headers.len()).collect()),
+            (true, _) => None,
+        };
+        if let Some(indices) = indices.as_mut() {
+            indices.retain(|i| !exclude.contains(i));
+        }
+
+        let headers = match headers {
+            Some(headers) => {
+                let mut headers: Vec<String> = match &indices {
+                    Some(indices) => indices.iter().map(|&i| headers[i].to_string()).collect(),
+                    None => headers.iter().map(str::to_string).collect(),
+                };
+                for (old, new) in &selection.rename {
+                    let header = headers
+                        .iter_mut()
+                        .find(|h| *h == old)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown column `{}` in --rename", old))?;
+                    *header = new.clone();
+                }
+                Some(StringRecord::from(headers))
+            }
+            None if !selection.rename.is_empty() => {
+                return Err(anyhow::anyhow!("--rename needs a header row"))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            indices,
+            exclude: if headers.is_some() { vec![] } else { exclude },
+            headers,
+        })
+    }
+
+    pub(super) fn headers(&self) -> Option<&StringRecord> {
+        self.headers.as_ref()
+    }
+
+    pub(super) fn apply(&self, record: &StringRecord) -> StringRecord {
+        match &self.indices {
+            Some(indices) => indices
+                .iter()
+                .map(|&i| record.get(i).unwrap_or_default())
+                .collect(),
+            None if self.exclude.is_empty() => record.clone(),
+            None => record
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !self.exclude.contains(i))
+                .map(|(_, cell)| cell)
+                .collect(),
+        }
+    }
+}
+
+impl CsvRecord {
+    pub(super) fn project(self, selection: &ColumnSelection) -> anyhow::Result<Self> {
+        let projection = Projection::new(selection, self.headers.as_ref())?;
+        Ok(Self {
+            headers: projection.headers().cloned(),
+            records: self.records.iter().map(|r| projection.apply(r)).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_record(headers: Option<Vec<&str>>) -> CsvRecord {
+        CsvRecord {
+            headers: headers.map(StringRecord::from),
+            records: vec![StringRecord::from(vec!["Perin", "Goalkeeper", "37"])],
+        }
+    }
+
+    #[test]
+    fn test_project_select_reorders_and_renames() {
+        let selection = ColumnSelection {
+            select: vec!["Kit Number".to_string(), "Name".to_string()],
+            rename: vec![("Kit Number".to_string(), "kit".to_string())],
+            ..Default::default()
+        };
+        let projected = csv_record(Some(vec!["Name", "Position", "Kit Number"]))
+            .project(&selection)
+            .unwrap();
+        assert_eq!(projected.headers.unwrap(), vec!["kit", "Name"]);
+        assert_eq!(projected.records[0], vec!["37", "Perin"]);
+    }
+
+    #[test]
+    fn test_project_exclude_by_index_without_header() {
+        let selection = ColumnSelection {
+            exclude: vec!["1".to_string()],
+            ..Default::default()
+        };
+        let projected = csv_record(None).project(&selection).unwrap();
+        assert!(projected.headers.is_none());
+        assert_eq!(projected.records[0], vec!["Perin", "37"]);
+    }
+
+    #[test]
+    fn test_project_unknown_column() {
+        let selection = ColumnSelection {
+            select: vec!["Age".to_string()],
+            ..Default::default()
+        };
+        let err = csv_record(Some(vec!["Name", "Position", "Kit Number"]))
+            .project(&selection)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unknown column `Age`");
+    }
+}
@@ -0,0 +1,250 @@
+use super::CsvConfig;
+use crate::CsvType;
+use chardetng::EncodingDetector;
+use csv::{ReaderBuilder, StringRecord};
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::{
+    fmt,
+    io::{Cursor, Read},
+};
+
+/// Bytes looked at to detect the encoding and the dialect.
+const SAMPLE_SIZE: usize = 64 * 1024;
+/// Records of the sample parsed per candidate delimiter.
+const SAMPLE_RECORDS: usize = 1_000;
+const DELIMITERS: [u8; 5] = [b',', b';', b'\t', b'|', b':'];
+
+/// How a CSV file is written, either given on the command line or detected from its start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Dialect {
+    pub(super) delimiter: u8,
+    pub(super) quote: u8,
+    pub(super) has_headers: bool,
+    pub(super) encoding: &'static Encoding,
+}
+
+impl Dialect {
+    /// Work out the dialect from a sample of `reader`, keeping whatever `config` already
+    /// sets, and return it with a reader that yields the whole input as UTF-8, BOM removed.
+    pub(super) fn detect<R: Read + 'static>(
+        mut reader: R,
+        config: &CsvConfig,
+    ) -> anyhow::Result<(Self, Box<dyn Read>)> {
+        let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+        (&mut reader)
+            .take(SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)?;
+        let truncated = sample.len() == SAMPLE_SIZE;
+
+        let encoding = match &config.encoding {
+            Some(label) => Encoding::for_label(label.trim().as_bytes())
+                .ok_or_else(|| anyhow::anyhow!("Unknown encoding `{}`", label))?,
+            None => detect_encoding(&sample, truncated),
+        };
+        let (text, _) = encoding.decode_with_bom_removal(&sample);
+        // a cut off sample ends in a partial record, leave it out
+        let text = match truncated {
+            true => text.rfind('\n').map_or(&*text, |end| &text[..end]),
+            false => &text,
+        };
+
+        let quote = config.quote.map(|q| q as u8);
+        let delimiter = match config.delimiter {
+            Some(d) => d as u8,
+            None => detect_delimiter(text, quote.unwrap_or(b'"')),
+        };
+        let quote = quote.unwrap_or_else(|| detect_quote(text, delimiter));
+        let has_headers = config
+            .headers
+            .unwrap_or_else(|| detect_header(text, delimiter, quote));
+        let dialect = Self {
+            delimiter,
+            quote,
+            has_headers,
+            encoding,
+        };
+
+        let reader = DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding))
+            .utf8_passthru(true)
+            .strip_bom(true)
+            .build(Cursor::new(sample).chain(reader));
+        Ok((dialect, Box::new(reader)))
+    }
+
+    pub(super) fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .has_headers(self.has_headers);
+        builder
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "delimiter {:?}, quote {:?}, header {}, encoding {}",
+            self.delimiter as char,
+            self.quote as char,
+            if self.has_headers { "yes" } else { "no" },
+            self.encoding.name()
+        )
+    }
+}
+
+/// A BOM wins, then UTF-8 if the sample is valid, then the statistical guess of chardetng.
+fn detect_encoding(sample: &[u8], truncated: bool) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => return UTF_8,
+        // only the last character is incomplete because the sample was cut there
+        Err(e) if truncated && e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(sample, !truncated);
+    detector.guess(None, true)
+}
+
+fn sample_records(text: &str, delimiter: u8, quote: u8) -> Vec<StringRecord> {
+    ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quote(quote)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .into_records()
+        .take(SAMPLE_RECORDS)
+        .map_while(Result::ok)
+        .collect()
+}
+
+/// The delimiter that splits the most records into the same number of fields, more than
+/// one. Ties go to more fields, then to the earlier candidate; `,` when nothing fits.
+fn detect_delimiter(text: &str, quote: u8) -> u8 {
+    let mut best = (b',', 0, 0);
+    for delimiter in DELIMITERS {
+        let mut counts = std::collections::HashMap::new();
+        for record in sample_records(text, delimiter, quote) {
+            *counts.entry(record.len()).or_insert(0) += 1;
+        }
+        let Some((fields, rows)) = counts
+            .into_iter()
+            .filter(|(fields, _)| *fields > 1)
+            .max_by_key(|(fields, rows)| (*rows, *fields))
+        else {
+            continue;
+        };
+        if (rows, fields) > (best.1, best.2) {
+            best = (delimiter, rows, fields);
+        }
+    }
+    best.0
+}
+
+/// `'` when more fields start with a single than a double quote, `"` otherwise.
+fn detect_quote(text: &str, delimiter: u8) -> u8 {
+    let opening = |quote: u8| {
+        let bytes = text.as_bytes();
+        (0..bytes.len())
+            .filter(|&i| bytes[i] == quote)
+            .filter(|&i| {
+                i == 0 || matches!(bytes[i - 1], b'\n' | b'\r') || bytes[i - 1] == delimiter
+            })
+            .count()
+    };
+    if opening(b'\'') > opening(b'"') {
+        b'\''
+    } else {
+        b'"'
+    }
+}
+
+/// Whether the first row looks like a header.
+///
+/// Every column whose other rows share a type other than string votes: a first cell of
+/// another type counts for a header, one of the same type against. Without a clear vote
+/// against, the first row is taken as a header, as before detection existed.
+fn detect_header(text: &str, delimiter: u8, quote: u8) -> bool {
+    let records = sample_records(text, delimiter, quote);
+    let Some((first, rest)) = records.split_first() else {
+        return true;
+    };
+    let mut votes = 0;
+    for (i, cell) in first.iter().enumerate() {
+        let column = CsvType::infer(rest.iter().filter_map(|r| r.get(i)));
+        if column == CsvType::String || rest.iter().all(|r| r.get(i).is_none_or(str::is_empty)) {
+            continue;
+        }
+        if cell.is_empty() || column.merge(CsvType::of(cell)) == column {
+            votes -= 1;
+        } else {
+            votes += 1;
+        }
+    }
+    votes >= 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(data: &[u8]) -> (Dialect, String) {
+        let (dialect, mut reader) =
+            Dialect::detect(Cursor::new(data.to_vec()), &CsvConfig::default()).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        (dialect, text)
+    }
+
+    #[test]
+    fn test_detect_semicolon_and_tab() {
+        let (dialect, _) = detect(b"Name;Kit Number;Value\nPerin;37;1,5\nBuffon;1;2,25\n");
+        assert_eq!(dialect.delimiter, b';');
+        assert!(dialect.has_headers);
+        let (dialect, _) = detect(b"Perin\t37\nBuffon\t1\n");
+        assert_eq!(dialect.delimiter, b'\t');
+        assert!(!dialect.has_headers);
+    }
+
+    #[test]
+    fn test_detect_single_quote() {
+        let (dialect, _) = detect(b"'a,b',1\n'c',2\n");
+        assert_eq!((dialect.delimiter, dialect.quote), (b',', b'\''));
+        let mut rdr = dialect.reader_builder().from_reader("'a,b',1\n".as_bytes());
+        assert_eq!(&rdr.headers().unwrap()[0], "a,b");
+    }
+
+    #[test]
+    fn test_transcode_windows_1252_and_strip_bom() {
+        let data = "Name,City\nMüller,Köln\nFrançois,Besançon\nJosé,Málaga\nGroße,Düsseldorf\n";
+        let (encoded, _, _) = encoding_rs::WINDOWS_1252.encode(data);
+        let (dialect, text) = detect(&encoded);
+        assert_eq!(dialect.encoding, encoding_rs::WINDOWS_1252);
+        assert!(text.contains("Müller,Köln"));
+
+        let (dialect, text) = detect(b"\xef\xbb\xbfName,Kit\nPerin,37\n");
+        assert_eq!(dialect.encoding, UTF_8);
+        assert_eq!(text, "Name,Kit\nPerin,37\n");
+    }
+
+    #[test]
+    fn test_given_options_win() {
+        let config = CsvConfig {
+            delimiter: Some('|'),
+            headers: Some(false),
+            encoding: Some("gbk".to_string()),
+            ..Default::default()
+        };
+        let (dialect, _) = Dialect::detect(Cursor::new(b"a,b\n1,2\n".to_vec()), &config).unwrap();
+        assert_eq!(dialect.delimiter, b'|');
+        assert!(!dialect.has_headers);
+        assert_eq!(dialect.encoding, encoding_rs::GBK);
+    }
+}
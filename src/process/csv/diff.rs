@@ -0,0 +1,313 @@
+use super::{column_index, csv_output, read_csv, write_contents, CsvConfig, CsvRecord};
+use crate::OutputFormat;
+use csv::StringRecord;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Rows added, removed and changed between two versions of a file, matched by key.
+#[derive(Debug, Serialize)]
+pub struct CsvDiff {
+    #[serde(skip)]
+    pub key_columns: Vec<String>,
+    pub added: Vec<Map<String, Value>>,
+    pub removed: Vec<Map<String, Value>>,
+    pub changed: Vec<RowChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowChange {
+    pub key: Map<String, Value>,
+    /// Old and new value of every changed cell, by column.
+    pub changes: Map<String, Value>,
+}
+
+/// One side of the diff with its column names and rows by key.
+struct DiffSide {
+    columns: Vec<String>,
+    keys: Vec<usize>,
+    records: Vec<StringRecord>,
+}
+
+impl DiffSide {
+    fn new(csv_record: CsvRecord, key: &[String], file: &str) -> anyhow::Result<Self> {
+        let headers = csv_record.headers.as_ref();
+        let keys = key
+            .iter()
+            .map(|c| column_index(headers, c).map_err(|e| anyhow::anyhow!("{} of {}", e, file)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let width = csv_record
+            .headers
+            .iter()
+            .chain(&csv_record.records)
+            .map(|r| r.len())
+            .max()
+            .unwrap_or(0);
+        let columns = (0..width)
+            .map(|i| {
+                headers
+                    .and_then(|h| h.get(i))
+                    .map_or_else(|| i.to_string(), str::to_string)
+            })
+            .collect();
+        Ok(Self {
+            columns,
+            keys,
+            records: csv_record.records,
+        })
+    }
+
+    fn key_columns(&self) -> Vec<String> {
+        self.keys.iter().map(|&i| self.columns[i].clone()).collect()
+    }
+
+    fn key(&self, record: &StringRecord) -> Vec<String> {
+        self.keys
+            .iter()
+            .map(|&i| record.get(i).unwrap_or_default().to_string())
+            .collect()
+    }
+
+    fn row(&self, record: &StringRecord) -> Map<String, Value> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.clone(), Value::from(record.get(i).unwrap_or_default())))
+            .collect()
+    }
+
+    /// Row positions by key, refusing keys that match more than one row.
+    fn index(&self, file: &str) -> anyhow::Result<HashMap<Vec<String>, usize>> {
+        let mut index = HashMap::new();
+        for (i, record) in self.records.iter().enumerate() {
+            if index.insert(self.key(record), i).is_some() {
+                return Err(anyhow::anyhow!(
+                    "Key {:?} is on more than one row of {}, pick a unique --key",
+                    self.key(record),
+                    file
+                ));
+            }
+        }
+        Ok(index)
+    }
+}
+
+/// Compare rows with the same key cell by cell over the columns of both files, a column
+/// missing from one side counts as empty there. Removed and changed rows come in the
+/// order of the old file, added ones in the order of the new file.
+fn csv_diff(
+    old: CsvRecord,
+    new: CsvRecord,
+    key: &[String],
+    files: (&str, &str),
+) -> anyhow::Result<CsvDiff> {
+    let old = DiffSide::new(old, key, files.0)?;
+    let new = DiffSide::new(new, key, files.1)?;
+    let new_index = new.index(files.1)?;
+    old.index(files.0)?;
+
+    let mut columns = old.columns.clone();
+    let seen: HashSet<_> = old.columns.iter().collect();
+    columns.extend(new.columns.iter().filter(|c| !seen.contains(c)).cloned());
+    let cell = |side: &DiffSide, record: &StringRecord, column: &str| {
+        side.columns
+            .iter()
+            .position(|c| c == column)
+            .and_then(|i| record.get(i))
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut diff = CsvDiff {
+        key_columns: old.key_columns(),
+        added: vec![],
+        removed: vec![],
+        changed: vec![],
+    };
+    let mut matched = HashSet::new();
+    for record in &old.records {
+        let key = old.key(record);
+        let Some(&i) = new_index.get(&key) else {
+            diff.removed.push(old.row(record));
+            continue;
+        };
+        matched.insert(i);
+        let new_record = &new.records[i];
+        let changes: Map<String, Value> = columns
+            .iter()
+            .filter_map(|column| {
+                let (before, after) = (cell(&old, record, column), cell(&new, new_record, column));
+                (before != after).then(|| (column.clone(), json!({"old": before, "new": after})))
+            })
+            .collect();
+        if !changes.is_empty() {
+            let key = diff
+                .key_columns
+                .iter()
+                .cloned()
+                .zip(key.into_iter().map(Value::from));
+            diff.changed.push(RowChange {
+                key: key.collect(),
+                changes,
+            });
+        }
+    }
+    for (i, record) in new.records.iter().enumerate() {
+        if !matched.contains(&i) {
+            diff.added.push(new.row(record));
+        }
+    }
+    Ok(diff)
+}
+
+impl From<CsvDiff> for CsvRecord {
+    /// One line per changed cell, and one per added or removed row with its cells
+    /// in the `new` or `old` column.
+    fn from(diff: CsvDiff) -> Self {
+        let joined = |row: &Map<String, Value>, columns: Option<&[String]>| {
+            row.iter()
+                .filter(|(column, _)| columns.is_none_or(|c| c.contains(column)))
+                .map(|(column, value)| format!("{}={}", column, value.as_str().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let key = |row| joined(row, Some(&diff.key_columns));
+        let mut records = Vec::new();
+        for change in &diff.changed {
+            let key = joined(&change.key, None);
+            for (column, cells) in &change.changes {
+                let cell = |side: &str| cells[side].as_str().unwrap_or_default().to_string();
+                records.push(StringRecord::from(vec![
+                    "changed".to_string(),
+                    key.clone(),
+                    column.clone(),
+                    cell("old"),
+                    cell("new"),
+                ]));
+            }
+        }
+        for row in &diff.removed {
+            let (key, row) = (key(row), joined(row, None));
+            records.push(StringRecord::from(vec!["removed", &key, "", &row, ""]));
+        }
+        for row in &diff.added {
+            let (key, row) = (key(row), joined(row, None));
+            records.push(StringRecord::from(vec!["added", &key, "", "", &row]));
+        }
+        Self {
+            headers: Some(StringRecord::from(vec![
+                "change", "key", "column", "old", "new",
+            ])),
+            records,
+        }
+    }
+}
+
+pub fn process_csv_diff(
+    old: &str,
+    new: &str,
+    output: &str,
+    format: OutputFormat,
+    key: &[String],
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let diff = csv_diff(
+        read_csv(old, config)?,
+        read_csv(new, config)?,
+        key,
+        (old, new),
+    )?;
+    eprintln!(
+        "{} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+    let contents = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(&diff)?,
+        OutputFormat::Jsonl => {
+            let removed = diff
+                .removed
+                .iter()
+                .map(|row| json!({"change": "removed", "row": row}));
+            let changed = diff.changed.iter().map(
+                |change| json!({"change": "changed", "key": change.key, "changes": change.changes}),
+            );
+            let added = diff
+                .added
+                .iter()
+                .map(|row| json!({"change": "added", "row": row}));
+            removed
+                .chain(changed)
+                .chain(added)
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&diff)?,
+        OutputFormat::Toml => toml::to_string(&diff)?,
+        format => return csv_output(diff.into(), output, format, config),
+    };
+    write_contents(output, &contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(data: &str) -> CsvRecord {
+        csv::Reader::from_reader(data.as_bytes())
+            .try_into()
+            .unwrap()
+    }
+
+    fn diff(old: &str, new: &str) -> anyhow::Result<CsvDiff> {
+        csv_diff(
+            record(old),
+            record(new),
+            &["id".to_string()],
+            ("old", "new"),
+        )
+    }
+
+    #[test]
+    fn test_diff_rows_and_cells() {
+        let diff = diff(
+            "id,name,kit\n1,Perin,37\n2,Buffon,77\n3,Pinsoglio,31\n",
+            "id,name,kit\n1,Perin,1\n3,Pinsoglio,31\n4,Szczesny,1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&diff).unwrap(),
+            json!({
+                "added": [{"id": "4", "name": "Szczesny", "kit": "1"}],
+                "removed": [{"id": "2", "name": "Buffon", "kit": "77"}],
+                "changed": [{"key": {"id": "1"}, "changes": {"kit": {"old": "37", "new": "1"}}}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_added_column_and_table() {
+        let diff = diff(
+            "id,name\n1,Perin\n",
+            "id,name,kit\n1,Perin,37\n2,Buffon,1\n",
+        )
+        .unwrap();
+        let csv_record = CsvRecord::from(diff);
+        assert_eq!(
+            csv_record.records,
+            vec![
+                vec!["changed", "id=1", "kit", "", "37"],
+                vec!["added", "id=2", "", "", "id=2, name=Buffon, kit=1"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_duplicate_key() {
+        let err = diff("id\n1\n1\n", "id\n1\n").unwrap_err();
+        assert!(err.to_string().contains("more than one row of old"));
+    }
+}
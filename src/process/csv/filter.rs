@@ -0,0 +1,332 @@
+use super::column_index;
+use csv::StringRecord;
+use regex::Regex;
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+/// A compiled `--where` expression.
+///
+/// Predicates compare a column with a value, `Position == "Goalkeeper"`, `Age > 30`,
+/// `Name ~ "^A"`, and are combined with `and`, `or`, `not` and parentheses. Column names
+/// with spaces or symbols are quoted with backticks: `` `Kit Number` >= 10 ``.
+#[derive(Debug)]
+pub(super) enum RowFilter {
+    Compare(Predicate),
+    Not(Box<RowFilter>),
+    And(Box<RowFilter>, Box<RowFilter>),
+    Or(Box<RowFilter>, Box<RowFilter>),
+}
+
+#[derive(Debug)]
+pub(super) struct Predicate {
+    index: usize,
+    op: CmpOp,
+    value: String,
+    number: Option<f64>,
+    regex: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Column(String),
+    Str(String),
+    Op(CmpOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl RowFilter {
+    pub(super) fn new(expr: &str, headers: Option<&StringRecord>) -> anyhow::Result<Self> {
+        let tokens = tokenize(expr)?;
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+            headers,
+        };
+        let filter = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(filter),
+            Some(token) => Err(anyhow::anyhow!("Unexpected {:?} in --where", token)),
+        }
+    }
+
+    pub(super) fn matches(&self, record: &StringRecord) -> anyhow::Result<bool> {
+        match self {
+            Self::Compare(predicate) => predicate.matches(record),
+            Self::Not(inner) => Ok(!inner.matches(record)?),
+            Self::And(a, b) => Ok(a.matches(record)? && b.matches(record)?),
+            Self::Or(a, b) => Ok(a.matches(record)? || b.matches(record)?),
+        }
+    }
+}
+
+impl Predicate {
+    fn matches(&self, record: &StringRecord) -> anyhow::Result<bool> {
+        let cell = record.get(self.index).unwrap_or_default();
+        if let Some(regex) = &self.regex {
+            return Ok(regex.is_match(cell) == (self.op == CmpOp::Match));
+        }
+
+        let ordering = match self.number {
+            Some(number) => match cell.trim().parse::<f64>() {
+                Ok(cell) => cell.partial_cmp(&number),
+                // a blank or `n/a` cell is never more or less than a number, and only
+                // equal to it as text
+                Err(_) => {
+                    return Ok(match self.op {
+                        CmpOp::Eq => cell == self.value,
+                        CmpOp::Ne => cell != self.value,
+                        _ => false,
+                    })
+                }
+            },
+            None => Some(cell.cmp(self.value.as_str())),
+        };
+
+        Ok(match (self.op, ordering) {
+            (CmpOp::Eq, ordering) => ordering == Some(Ordering::Equal),
+            (CmpOp::Ne, ordering) => ordering != Some(Ordering::Equal),
+            (_, None) => false,
+            (CmpOp::Gt, Some(ordering)) => ordering.is_gt(),
+            (CmpOp::Ge, Some(ordering)) => ordering.is_ge(),
+            (CmpOp::Lt, Some(ordering)) => ordering.is_lt(),
+            (CmpOp::Le, Some(ordering)) => ordering.is_le(),
+            (CmpOp::Match | CmpOp::NotMatch, _) => unreachable!("regex is compiled for ~ and !~"),
+        })
+    }
+}
+
+struct Parser<'a, I: Iterator<Item = Token>> {
+    tokens: Peekable<I>,
+    headers: Option<&'a StringRecord>,
+}
+
+impl<I: Iterator<Item = Token>> Parser<'_, I> {
+    fn or(&mut self) -> anyhow::Result<RowFilter> {
+        let mut filter = self.and()?;
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            filter = RowFilter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> anyhow::Result<RowFilter> {
+        let mut filter = self.unary()?;
+        while self.tokens.next_if_eq(&Token::And).is_some() {
+            filter = RowFilter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<RowFilter> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(RowFilter::Not(Box::new(self.unary()?))),
+            Some(Token::LParen) => {
+                let filter = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::RParen) => Ok(filter),
+                    _ => Err(anyhow::anyhow!("Missing `)` in --where")),
+                }
+            }
+            Some(Token::Word(column) | Token::Column(column)) => self.predicate(column),
+            Some(token) => Err(anyhow::anyhow!(
+                "Expected a column name in --where, found {:?}",
+                token
+            )),
+            None => Err(anyhow::anyhow!("Unexpected end of --where")),
+        }
+    }
+
+    fn predicate(&mut self, column: String) -> anyhow::Result<RowFilter> {
+        let index =
+            column_index(self.headers, &column).map_err(|e| anyhow::anyhow!("{} in --where", e))?;
+        let Some(Token::Op(op)) = self.tokens.next() else {
+            return Err(anyhow::anyhow!(
+                "Expected a comparison after column `{}` in --where",
+                column
+            ));
+        };
+        let (value, quoted) = match self.tokens.next() {
+            Some(Token::Word(value)) => (value, false),
+            Some(Token::Str(value)) => (value, true),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Expected a value to compare column `{}` with in --where",
+                    column
+                ))
+            }
+        };
+
+        let regex = match op {
+            CmpOp::Match | CmpOp::NotMatch => Some(Regex::new(&value).map_err(|e| {
+                anyhow::anyhow!("Invalid pattern for column `{}` in --where: {}", column, e)
+            })?),
+            _ => None,
+        };
+        let number = match quoted {
+            false if regex.is_none() => value.parse().ok(),
+            _ => None,
+        };
+
+        Ok(RowFilter::Compare(Predicate {
+            index,
+            op,
+            value,
+            number,
+            regex,
+        }))
+    }
+}
+
+fn tokenize(expr: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' => {
+                chars.next();
+                if c == '(' {
+                    Token::LParen
+                } else {
+                    Token::RParen
+                }
+            }
+            '"' | '\'' => Token::Str(quoted(&mut chars)?),
+            '`' => Token::Column(quoted(&mut chars)?),
+            '=' | '!' | '<' | '>' | '~' | '&' | '|' => operator(&mut chars)?,
+            _ => {
+                let mut word = String::new();
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !"()\"'`=!<>~&|".contains(*c))
+                {
+                    word.push(c);
+                }
+                match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn quoted(chars: &mut Peekable<Chars>) -> anyhow::Result<String> {
+    let quote = chars.next().expect("called on a quote");
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('\\') => value.extend(chars.next()),
+            Some(c) if c == quote => return Ok(value),
+            Some(c) => value.push(c),
+            None => return Err(anyhow::anyhow!("Unterminated {} in --where", quote)),
+        }
+    }
+}
+
+fn operator(chars: &mut Peekable<Chars>) -> anyhow::Result<Token> {
+    let first = chars.next().expect("called on an operator");
+    let second = chars.next_if(|c| "=~&|".contains(*c));
+    let token = match (first, second) {
+        ('=', Some('=')) | ('=', None) => Token::Op(CmpOp::Eq),
+        ('!', Some('=')) => Token::Op(CmpOp::Ne),
+        ('!', Some('~')) => Token::Op(CmpOp::NotMatch),
+        ('>', Some('=')) => Token::Op(CmpOp::Ge),
+        ('>', None) => Token::Op(CmpOp::Gt),
+        ('<', Some('=')) => Token::Op(CmpOp::Le),
+        ('<', None) => Token::Op(CmpOp::Lt),
+        ('~', None) => Token::Op(CmpOp::Match),
+        ('&', Some('&')) => Token::And,
+        ('|', Some('|')) => Token::Or,
+        ('!', None) => Token::Not,
+        (first, second) => {
+            return Err(anyhow::anyhow!(
+                "Invalid operator `{}{}` in --where",
+                first,
+                second.map(String::from).unwrap_or_default()
+            ))
+        }
+    };
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers() -> StringRecord {
+        StringRecord::from(vec!["Name", "Position", "Kit Number"])
+    }
+
+    fn matches(expr: &str, row: Vec<&str>) -> bool {
+        let filter = RowFilter::new(expr, Some(&headers())).unwrap();
+        filter.matches(&StringRecord::from(row)).unwrap()
+    }
+
+    #[test]
+    fn test_filter_compare() {
+        let row = vec!["Mattia Perin", "Goalkeeper", "37"];
+        assert!(matches(r#"Position == "Goalkeeper""#, row.clone()));
+        assert!(matches("`Kit Number` > 9", row.clone()));
+        assert!(!matches("`Kit Number` <= 9", row.clone()));
+        assert!(matches(r#"Name ~ "^M""#, row.clone()));
+        assert!(matches(r#"Name !~ "^A""#, row));
+    }
+
+    #[test]
+    fn test_filter_and_or_precedence() {
+        let row = vec!["Mattia Perin", "Goalkeeper", "37"];
+        assert!(matches(
+            r#"Position == "Defender" or Name ~ "Perin" and `Kit Number` == 37"#,
+            row.clone()
+        ));
+        assert!(!matches(
+            r#"(Position == "Defender" or Name ~ "Perin") and not `Kit Number` == 37"#,
+            row
+        ));
+    }
+
+    #[test]
+    fn test_filter_unknown_column() {
+        let err = RowFilter::new("Age > 30", Some(&headers())).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown column `Age` in --where");
+    }
+
+    #[test]
+    fn test_filter_non_numeric_cell() {
+        for cell in ["n/a", ""] {
+            let row = vec!["Perin", "Goalkeeper", cell];
+            assert!(!matches("`Kit Number` > 30", row.clone()));
+            assert!(!matches("`Kit Number` <= 30", row.clone()));
+            assert!(!matches("`Kit Number` == 1", row.clone()));
+            assert!(matches("`Kit Number` != 1", row));
+        }
+        assert!(matches(
+            "`Kit Number` == n/a",
+            vec!["Perin", "Goalkeeper", "n/a"]
+        ));
+    }
+}
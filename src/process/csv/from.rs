@@ -0,0 +1,154 @@
+use super::{nested::flatten, CsvRecord};
+use crate::{get_decompressed_reader, get_writer, InputFormat};
+use csv::{StringRecord, Terminator};
+use serde_json::{Map, Value};
+use std::{collections::HashSet, io::Read};
+
+fn parse_input(contents: &str, format: InputFormat) -> anyhow::Result<Value> {
+    let value = match format {
+        InputFormat::Json => serde_json::from_str(contents)?,
+        InputFormat::Yaml => serde_yaml::from_str(contents)?,
+        InputFormat::Toml => toml::from_str(contents)?,
+    };
+    Ok(value)
+}
+
+/// Pick the rows out of a parsed document.
+///
+/// A top level array is taken as is. An object wrapping a single array, such as the
+/// `items` table written by `--format toml`, is unwrapped; any other object is one row.
+fn into_rows(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Object(map) if map.len() == 1 => match map.into_iter().next() {
+            Some((_, Value::Array(items))) => items,
+            Some((key, value)) => vec![Value::Object(Map::from_iter([(key, value)]))],
+            None => vec![],
+        },
+        value => vec![value],
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+impl TryFrom<Vec<Value>> for CsvRecord {
+    type Error = anyhow::Error;
+
+    /// Objects become rows under the union of their keys, in first-seen order.
+    /// Arrays become header-less rows.
+    fn try_from(rows: Vec<Value>) -> Result<Self, Self::Error> {
+        if rows.iter().all(Value::is_array) {
+            let records = rows
+                .iter()
+                .filter_map(Value::as_array)
+                .map(|row| row.iter().map(cell).collect())
+                .collect();
+            return Ok(Self {
+                headers: None,
+                records,
+            });
+        }
+
+        let mut seen = HashSet::new();
+        let mut headers = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let Value::Object(map) = row else {
+                return Err(anyhow::anyhow!(
+                    "Row {} is not an object, expected an array of objects",
+                    i + 1
+                ));
+            };
+            for key in map.keys() {
+                if seen.insert(key.as_str()) {
+                    headers.push(key.clone());
+                }
+            }
+        }
+
+        let records = rows
+            .iter()
+            .map(|row| {
+                headers
+                    .iter()
+                    .map(|key| row.get(key).map(cell).unwrap_or_default())
+                    .collect::<StringRecord>()
+            })
+            .collect();
+
+        Ok(Self {
+            headers: Some(StringRecord::from(headers)),
+            records,
+        })
+    }
+}
+
+pub fn process_csv_from(
+    input: &str,
+    output: &str,
+    format: InputFormat,
+    delimiter: char,
+    flatten_rows: bool,
+) -> anyhow::Result<()> {
+    let mut contents = String::new();
+    get_decompressed_reader(input)?.read_to_string(&mut contents)?;
+
+    let mut rows = into_rows(parse_input(&contents, format)?);
+    if flatten_rows {
+        rows = rows.into_iter().map(flatten).collect();
+    }
+    let csv_record = CsvRecord::try_from(rows)?;
+    let mut writer = get_writer(output)?;
+    csv_record.write_csv(&mut writer, delimiter, Terminator::Any(b'\n'))?;
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(contents: &str, format: InputFormat) -> String {
+        let csv_record = CsvRecord::try_from(into_rows(parse_input(contents, format).unwrap()));
+        let mut buf = Vec::new();
+        csv_record
+            .unwrap()
+            .write_csv(&mut buf, ',', Terminator::Any(b'\n'))
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_json_to_csv_union_of_keys() {
+        let json = r#"[{"name": "Perin", "kit": 37}, {"name": "Buffon, G", "active": false}]"#;
+        assert_eq!(
+            convert(json, InputFormat::Json),
+            "name,kit,active\nPerin,37,\n\"Buffon, G\",,false\n"
+        );
+    }
+
+    #[test]
+    fn test_yaml_to_csv() {
+        let yaml = "- name: Perin\n  tags: [gk]\n- name: Szczesny\n";
+        assert_eq!(
+            convert(yaml, InputFormat::Yaml),
+            "name,tags\nPerin,\"[\"\"gk\"\"]\"\nSzczesny,\n"
+        );
+    }
+
+    #[test]
+    fn test_toml_items_to_csv() {
+        let toml = "[[items]]\nname = \"Perin\"\nkit = 37\n";
+        assert_eq!(convert(toml, InputFormat::Toml), "name,kit\nPerin,37\n");
+    }
+
+    #[test]
+    fn test_scalar_rows_rejected() {
+        let rows = into_rows(parse_input("[1, 2]", InputFormat::Json).unwrap());
+        assert!(CsvRecord::try_from(rows).is_err());
+    }
+}
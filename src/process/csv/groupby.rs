@@ -0,0 +1,267 @@
+use super::{
+    column_index, csv_output, csv_reader, read_headers, read_rows, sort::compare_cells,
+    ColumnTypes, CsvConfig, CsvRecord,
+};
+use crate::{AggFunc, OutputFormat};
+use std::collections::{HashMap, HashSet};
+
+/// One `--agg` item: a function and, except for a plain `count`, the column it reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregate {
+    pub func: AggFunc,
+    pub column: Option<String>,
+}
+
+impl Aggregate {
+    fn name(&self) -> String {
+        match &self.column {
+            Some(column) => format!("{}({})", self.func.as_str(), column),
+            None => self.func.as_str().to_string(),
+        }
+    }
+}
+
+/// Running state of one aggregate within one group.
+#[derive(Debug)]
+enum Accumulator {
+    Count(usize),
+    Sum(f64),
+    Avg(f64, usize),
+    Min(Option<String>),
+    Max(Option<String>),
+    Distinct(HashSet<String>),
+}
+
+impl Accumulator {
+    fn new(func: AggFunc) -> Self {
+        match func {
+            AggFunc::Count => Self::Count(0),
+            AggFunc::Sum => Self::Sum(0.0),
+            AggFunc::Avg => Self::Avg(0.0, 0),
+            AggFunc::Min => Self::Min(None),
+            AggFunc::Max => Self::Max(None),
+            AggFunc::Distinct => Self::Distinct(HashSet::new()),
+        }
+    }
+
+    /// Add a cell, `None` for a row-counting `count`. Empty cells are skipped.
+    fn add(
+        &mut self,
+        cell: Option<&str>,
+        number: impl Fn(&str) -> anyhow::Result<f64>,
+    ) -> anyhow::Result<()> {
+        let cell = match cell {
+            Some("") => return Ok(()),
+            Some(cell) => cell,
+            None => {
+                if let Self::Count(n) = self {
+                    *n += 1;
+                }
+                return Ok(());
+            }
+        };
+        match self {
+            Self::Count(n) => *n += 1,
+            Self::Sum(sum) => *sum += number(cell)?,
+            Self::Avg(sum, n) => {
+                *sum += number(cell)?;
+                *n += 1;
+            }
+            Self::Min(min) => match min {
+                Some(current) if compare_cells(cell, current).is_ge() => {}
+                _ => *min = Some(cell.to_string()),
+            },
+            Self::Max(max) => match max {
+                Some(current) if compare_cells(cell, current).is_le() => {}
+                _ => *max = Some(cell.to_string()),
+            },
+            Self::Distinct(seen) => {
+                seen.insert(cell.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        match self {
+            Self::Count(n) => n.to_string(),
+            Self::Sum(sum) => sum.to_string(),
+            Self::Avg(_, 0) => String::new(),
+            Self::Avg(sum, n) => (sum / n as f64).to_string(),
+            Self::Min(cell) | Self::Max(cell) => cell.unwrap_or_default(),
+            Self::Distinct(seen) => seen.len().to_string(),
+        }
+    }
+}
+
+/// Aggregate rows per distinct combination of the `by` columns, groups in first-seen order.
+fn csv_groupby(
+    input: &str,
+    by: &[String],
+    aggs: &[Aggregate],
+    config: &CsvConfig,
+) -> anyhow::Result<CsvRecord> {
+    let mut rdr = csv_reader(input, config)?;
+    let headers = read_headers(&mut rdr)?;
+    let by_indices = by
+        .iter()
+        .map(|c| column_index(headers.as_ref(), c).map_err(|e| anyhow::anyhow!("{} in --by", e)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let agg_indices = aggs
+        .iter()
+        .map(|agg| match (&agg.column, agg.func) {
+            (Some(column), _) => column_index(headers.as_ref(), column)
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("{} in --agg", e)),
+            (None, AggFunc::Count) => Ok(None),
+            (None, func) => Err(anyhow::anyhow!(
+                "`{}` needs a column, as {}(COLUMN)",
+                func.as_str(),
+                func.as_str()
+            )),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut groups: Vec<(Vec<String>, Vec<Accumulator>)> = Vec::new();
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    for record in read_rows(rdr, headers.as_ref(), config)? {
+        let record = record?;
+        let key: Vec<String> = by_indices
+            .iter()
+            .map(|&i| record.get(i).unwrap_or_default().to_string())
+            .collect();
+        let group = *index.entry(key.clone()).or_insert_with(|| {
+            groups.push((key, aggs.iter().map(|a| Accumulator::new(a.func)).collect()));
+            groups.len() - 1
+        });
+        for ((agg, column), acc) in aggs.iter().zip(&agg_indices).zip(&mut groups[group].1) {
+            let number = |cell: &str| {
+                cell.trim().parse::<f64>().map_err(|_| {
+                    anyhow::anyhow!(
+                        "`{}` in column `{}` on line {} is not a number",
+                        cell,
+                        agg.column.as_deref().unwrap_or_default(),
+                        record.position().map_or(0, |p| p.line())
+                    )
+                })
+            };
+            acc.add(column.map(|i| record.get(i).unwrap_or_default()), number)?;
+        }
+    }
+
+    let names = by.iter().cloned().chain(aggs.iter().map(Aggregate::name));
+    let records = groups
+        .into_iter()
+        .map(|(key, accs)| {
+            key.into_iter()
+                .chain(accs.into_iter().map(Accumulator::finish))
+                .collect()
+        })
+        .collect();
+    Ok(CsvRecord {
+        headers: Some(names.collect::<Vec<_>>().into()),
+        records,
+    })
+}
+
+pub fn process_csv_groupby(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    by: &[String],
+    aggs: &[Aggregate],
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let grouped = csv_groupby(input, by, aggs, config)?;
+    let config = CsvConfig {
+        types: ColumnTypes {
+            infer: true,
+            columns: vec![],
+            nested: false,
+        },
+        ..config.clone()
+    };
+    csv_output(grouped, output, format, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::StringRecord;
+
+    fn agg(func: AggFunc, column: Option<&str>) -> Aggregate {
+        Aggregate {
+            func,
+            column: column.map(str::to_string),
+        }
+    }
+
+    fn row(grouped: &CsvRecord, key: &str) -> StringRecord {
+        grouped
+            .records
+            .iter()
+            .find(|r| &r[0] == key)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_groupby_nationality() {
+        let aggs = [
+            agg(AggFunc::Count, None),
+            agg(AggFunc::Avg, Some("Kit Number")),
+            agg(AggFunc::Max, Some("Kit Number")),
+            agg(AggFunc::Distinct, Some("Position")),
+        ];
+        let grouped = csv_groupby(
+            "assets/juventus.csv",
+            &["Nationality".to_string()],
+            &aggs,
+            &CsvConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            grouped.headers.as_ref().unwrap(),
+            vec![
+                "Nationality",
+                "count",
+                "avg(Kit Number)",
+                "max(Kit Number)",
+                "distinct(Position)"
+            ]
+        );
+        assert_eq!(row(&grouped, "Poland"), vec!["Poland", "1", "1", "1", "1"]);
+        let total: usize = grouped
+            .records
+            .iter()
+            .map(|r| r[1].parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(total, 27);
+    }
+
+    #[test]
+    fn test_groupby_rejects_text_sum() {
+        let err = csv_groupby(
+            "assets/juventus.csv",
+            &[],
+            &[agg(AggFunc::Sum, Some("Name"))],
+            &CsvConfig::default(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("in column `Name` on line 2 is not a number"));
+    }
+
+    #[test]
+    fn test_accumulator_min_max_numeric() {
+        let mut min = Accumulator::new(AggFunc::Min);
+        let mut max = Accumulator::new(AggFunc::Max);
+        for cell in ["9", "10", "", "2"] {
+            min.add(Some(cell), |c| Ok(c.parse()?)).unwrap();
+            max.add(Some(cell), |c| Ok(c.parse()?)).unwrap();
+        }
+        assert_eq!(min.finish(), "2");
+        assert_eq!(max.finish(), "10");
+    }
+}
@@ -0,0 +1,247 @@
+use super::{column_index, csv_output, read_csv, CsvConfig, CsvRecord};
+use crate::{JoinKind, OutputFormat};
+use csv::StringRecord;
+use std::collections::HashMap;
+
+/// A join key, the same column name on both sides or a `left=right` pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinKey {
+    pub left: String,
+    pub right: String,
+}
+
+fn key_indices(
+    keys: &[JoinKey],
+    headers: Option<&StringRecord>,
+    side: fn(&JoinKey) -> &str,
+) -> anyhow::Result<Vec<usize>> {
+    keys.iter()
+        .map(|key| column_index(headers, side(key)))
+        .collect()
+}
+
+/// The key of `record`, or none when a key cell is empty: like NULL in SQL, an empty key
+/// matches nothing, not even another empty key.
+fn key_of(record: &StringRecord, indices: &[usize]) -> Option<Vec<String>> {
+    indices
+        .iter()
+        .map(|&i| {
+            let cell = record.get(i).unwrap_or_default();
+            (!cell.is_empty()).then(|| cell.to_string())
+        })
+        .collect()
+}
+
+/// The cells of `record` at `indices`, or empty cells for a missing row.
+fn cells<'a>(
+    record: Option<&'a StringRecord>,
+    indices: &'a [usize],
+) -> impl Iterator<Item = &'a str> + 'a {
+    indices
+        .iter()
+        .map(move |&i| record.and_then(|r| r.get(i)).unwrap_or_default())
+}
+
+/// Hash join: the right side is indexed by key, the left side drives the output order,
+/// and right rows without a match come last for right and full joins.
+fn csv_join(
+    left: CsvRecord,
+    right: CsvRecord,
+    keys: &[JoinKey],
+    kind: JoinKind,
+) -> anyhow::Result<CsvRecord> {
+    let left_keys = key_indices(keys, left.headers.as_ref(), |k| &k.left)?;
+    let right_keys = key_indices(keys, right.headers.as_ref(), |k| &k.right)?;
+    let left_width = left
+        .headers
+        .as_ref()
+        .or(left.records.first())
+        .map_or(0, |r| r.len());
+    let right_width = right
+        .headers
+        .as_ref()
+        .or(right.records.first())
+        .map_or(0, |r| r.len());
+    let right_rest: Vec<usize> = (0..right_width)
+        .filter(|i| !right_keys.contains(i))
+        .collect();
+
+    let headers = match (&left.headers, &right.headers) {
+        (Some(lh), Some(rh)) => {
+            let mut headers: Vec<String> = lh.iter().map(str::to_string).collect();
+            for &i in &right_rest {
+                let name = &rh[i];
+                headers.push(match lh.iter().any(|h| h == name) {
+                    true => format!("{}_right", name),
+                    false => name.to_string(),
+                });
+            }
+            Some(StringRecord::from(headers))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Both sides of a join need a header, or neither"
+            ))
+        }
+    };
+
+    let mut index: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+    for (i, record) in right.records.iter().enumerate() {
+        if let Some(key) = key_of(record, &right_keys) {
+            index.entry(key).or_default().push(i);
+        }
+    }
+    let mut matched = vec![false; right.records.len()];
+
+    let mut records = Vec::new();
+    for record in &left.records {
+        match key_of(record, &left_keys).and_then(|key| index.get(&key)) {
+            Some(matches) => {
+                for &i in matches {
+                    matched[i] = true;
+                    records.push(
+                        record
+                            .iter()
+                            .chain(cells(Some(&right.records[i]), &right_rest))
+                            .collect(),
+                    );
+                }
+            }
+            None if matches!(kind, JoinKind::Left | JoinKind::Full) => {
+                records.push(record.iter().chain(cells(None, &right_rest)).collect());
+            }
+            None => {}
+        }
+    }
+
+    if matches!(kind, JoinKind::Right | JoinKind::Full) {
+        for (record, _) in right.records.iter().zip(matched).filter(|(_, m)| !m) {
+            let mut row = vec![""; left_width];
+            for (&l, &r) in left_keys.iter().zip(&right_keys) {
+                row[l] = record.get(r).unwrap_or_default();
+            }
+            records.push(
+                row.into_iter()
+                    .chain(cells(Some(record), &right_rest))
+                    .collect(),
+            );
+        }
+    }
+
+    Ok(CsvRecord { headers, records })
+}
+
+pub fn process_csv_join(
+    left: &str,
+    right: &str,
+    output: &str,
+    format: OutputFormat,
+    keys: &[JoinKey],
+    kind: JoinKind,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let joined = csv_join(
+        read_csv(left, config)?,
+        read_csv(right, config)?,
+        keys,
+        kind,
+    )?;
+    csv_output(joined, output, format, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_record(rows: &[&[&str]]) -> CsvRecord {
+        CsvRecord {
+            headers: Some(StringRecord::from(rows[0].to_vec())),
+            records: rows[1..]
+                .iter()
+                .map(|r| StringRecord::from(r.to_vec()))
+                .collect(),
+        }
+    }
+
+    fn rows(joined: CsvRecord) -> Vec<Vec<String>> {
+        joined
+            .records
+            .iter()
+            .map(|r| r.iter().map(str::to_string).collect())
+            .collect()
+    }
+
+    fn join(kind: JoinKind) -> Vec<Vec<String>> {
+        let players = csv_record(&[
+            &["id", "Name"],
+            &["1", "Szczesny"],
+            &["37", "Perin"],
+            &["77", "Buffon"],
+        ]);
+        let stats = csv_record(&[
+            &["kit", "Name", "Goals"],
+            &["37", "M. Perin", "0"],
+            &["10", "P. Dybala", "5"],
+            &["1", "W. Szczesny", "0"],
+        ]);
+        let keys = [JoinKey {
+            left: "id".to_string(),
+            right: "kit".to_string(),
+        }];
+        let joined = csv_join(players, stats, &keys, kind).unwrap();
+        assert_eq!(
+            joined.headers.as_ref().unwrap(),
+            vec!["id", "Name", "Name_right", "Goals"]
+        );
+        rows(joined)
+    }
+
+    #[test]
+    fn test_join_inner() {
+        assert_eq!(
+            join(JoinKind::Inner),
+            vec![
+                vec!["1", "Szczesny", "W. Szczesny", "0"],
+                vec!["37", "Perin", "M. Perin", "0"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_left() {
+        let rows = join(JoinKind::Left);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2], vec!["77", "Buffon", "", ""]);
+    }
+
+    #[test]
+    fn test_join_full() {
+        let rows = join(JoinKind::Full);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[3], vec!["10", "", "P. Dybala", "5"]);
+        assert_eq!(join(JoinKind::Right).len(), 3);
+    }
+
+    #[test]
+    fn test_join_empty_keys_never_match() {
+        let left = || csv_record(&[&["id", "Name"], &["", "Perin"], &["1", "Szczesny"]]);
+        let right = || csv_record(&[&["id", "Goals"], &["", "0"], &["", "5"], &["1", "0"]]);
+        let keys = [JoinKey {
+            left: "id".to_string(),
+            right: "id".to_string(),
+        }];
+        let inner = csv_join(left(), right(), &keys, JoinKind::Inner).unwrap();
+        assert_eq!(rows(inner), vec![vec!["1", "Szczesny", "0"]]);
+        let full = csv_join(left(), right(), &keys, JoinKind::Full).unwrap();
+        assert_eq!(
+            rows(full),
+            vec![
+                vec!["", "Perin", ""],
+                vec!["1", "Szczesny", "0"],
+                vec!["", "", "0"],
+                vec!["", "", "5"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use super::CsvRecord;
+use csv::StringRecord;
+use std::io::Write;
+
+/// Text formats written as a prologue, one block per row and an epilogue, so they work
+/// the same whether the rows are in memory or streamed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Markup {
+    /// A GitHub flavored Markdown table.
+    Markdown,
+    /// An HTML `<table>`.
+    Html,
+    /// `<rows>` of `<row>` elements, one child element per column.
+    Xml,
+}
+
+pub(super) struct MarkupWriter<W: Write> {
+    markup: Markup,
+    writer: W,
+    /// Markdown column names or XML element names, `None` without a header.
+    names: Option<Vec<String>>,
+    rows: usize,
+}
+
+impl<W: Write> MarkupWriter<W> {
+    pub(super) fn new(
+        mut writer: W,
+        markup: Markup,
+        headers: Option<&StringRecord>,
+    ) -> anyhow::Result<Self> {
+        let names: Option<Vec<String>> = headers.map(|h| match markup {
+            Markup::Xml => h.iter().map(xml_name).collect(),
+            Markup::Markdown | Markup::Html => h.iter().map(str::to_string).collect(),
+        });
+        match (markup, &names) {
+            (Markup::Markdown, Some(names)) => markdown_header(&mut writer, names)?,
+            (Markup::Markdown, None) => {}
+            (Markup::Html, names) => {
+                writeln!(writer, "<table>")?;
+                if let Some(names) = names {
+                    writeln!(writer, "  <thead>")?;
+                    write!(writer, "    <tr>")?;
+                    for name in names {
+                        write!(writer, "<th>{}</th>", escape_html(name))?;
+                    }
+                    writeln!(writer, "</tr>")?;
+                    writeln!(writer, "  </thead>")?;
+                }
+                writeln!(writer, "  <tbody>")?;
+            }
+            (Markup::Xml, _) => {
+                writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+                writeln!(writer, "<rows>")?;
+            }
+        }
+        Ok(Self {
+            markup,
+            writer,
+            names,
+            rows: 0,
+        })
+    }
+
+    pub(super) fn write(&mut self, record: &StringRecord) -> anyhow::Result<()> {
+        let writer = &mut self.writer;
+        match self.markup {
+            Markup::Markdown => {
+                // GitHub needs a header row, so a headerless table gets a blank one
+                if self.names.is_none() && self.rows == 0 {
+                    markdown_header(&mut *writer, &vec![String::new(); record.len()])?;
+                }
+                let cells: Vec<String> = record.iter().map(escape_markdown).collect();
+                writeln!(writer, "| {} |", cells.join(" | "))?;
+            }
+            Markup::Html => {
+                write!(writer, "    <tr>")?;
+                for cell in record {
+                    write!(writer, "<td>{}</td>", escape_html(cell))?;
+                }
+                writeln!(writer, "</tr>")?;
+            }
+            Markup::Xml => {
+                writeln!(writer, "  <row>")?;
+                for (i, cell) in record.iter().enumerate() {
+                    let name = self
+                        .names
+                        .as_ref()
+                        .and_then(|names| names.get(i))
+                        .map_or("value", String::as_str);
+                    writeln!(writer, "    <{0}>{1}</{0}>", name, escape_html(cell))?;
+                }
+                writeln!(writer, "  </row>")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    pub(super) fn finish(mut self) -> anyhow::Result<W> {
+        match self.markup {
+            Markup::Markdown => {}
+            Markup::Html => {
+                writeln!(self.writer, "  </tbody>")?;
+                writeln!(self.writer, "</table>")?;
+            }
+            Markup::Xml => writeln!(self.writer, "</rows>")?,
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl CsvRecord {
+    pub(super) fn to_markup(&self, markup: Markup) -> anyhow::Result<String> {
+        let mut wtr = MarkupWriter::new(Vec::new(), markup, self.headers.as_ref())?;
+        for record in &self.records {
+            wtr.write(record)?;
+        }
+        let mut buf = wtr.finish()?;
+        // write_contents adds the final line break
+        buf.pop_if(|c| *c == b'\n');
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+fn markdown_header<W: Write>(mut writer: W, names: &[String]) -> anyhow::Result<()> {
+    let names: Vec<String> = names.iter().map(|n| escape_markdown(n)).collect();
+    writeln!(writer, "| {} |", names.join(" | "))?;
+    writeln!(writer, "|{}", " --- |".repeat(names.len()))?;
+    Ok(())
+}
+
+fn escape_markdown(cell: &str) -> String {
+    cell.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+fn escape_html(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    for c in cell.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A header turned into a valid XML element name, `Kit Number` becomes `Kit_Number`.
+fn xml_name(header: &str) -> String {
+    let mut name: String = header
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(markup: Markup, headers: Option<Vec<&str>>, records: &[Vec<&str>]) -> String {
+        let headers = headers.map(StringRecord::from);
+        let mut wtr = MarkupWriter::new(Vec::new(), markup, headers.as_ref()).unwrap();
+        for record in records {
+            wtr.write(&StringRecord::from(record.clone())).unwrap();
+        }
+        String::from_utf8(wtr.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let output = render(
+            Markup::Markdown,
+            Some(vec!["Name", "Kit Number"]),
+            &[vec!["Perin", "37"], vec!["a|b", "1"]],
+        );
+        assert_eq!(
+            output,
+            "| Name | Kit Number |\n| --- | --- |\n| Perin | 37 |\n| a\\|b | 1 |\n"
+        );
+        let output = render(Markup::Markdown, None, &[vec!["Perin", "37"]]);
+        assert_eq!(output, "|  |  |\n| --- | --- |\n| Perin | 37 |\n");
+    }
+
+    #[test]
+    fn test_html_table_without_header() {
+        let output = render(Markup::Html, None, &[vec!["<b>", "R&D"]]);
+        assert_eq!(
+            output,
+            "<table>\n  <tbody>\n    <tr><td>&lt;b&gt;</td><td>R&amp;D</td></tr>\n  </tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_xml_element_names() {
+        let output = render(
+            Markup::Xml,
+            Some(vec!["Kit Number", "1st"]),
+            &[vec!["37", "x"]],
+        );
+        assert!(output.contains("    <Kit_Number>37</Kit_Number>\n    <_1st>x</_1st>\n"));
+        let output = render(Markup::Xml, None, &[vec!["37"]]);
+        assert!(output.contains("    <value>37</value>\n"));
+    }
+}
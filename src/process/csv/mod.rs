@@ -0,0 +1,709 @@
+mod cat;
+mod columnar;
+mod columns;
+mod dialect;
+mod diff;
+mod filter;
+mod from;
+mod groupby;
+mod join;
+mod markup;
+mod mask;
+mod nested;
+mod query;
+mod sample;
+mod schema;
+mod sort;
+mod split;
+mod spreadsheet;
+mod sql;
+mod stats;
+mod table;
+mod types;
+
+pub use cat::*;
+pub use columns::*;
+pub use diff::*;
+pub use from::*;
+pub use groupby::*;
+pub use join::*;
+pub use mask::*;
+pub use query::*;
+pub use schema::*;
+pub use sort::SortKey;
+pub use split::*;
+pub use stats::*;
+pub use types::*;
+
+use columnar::write_columnar;
+use dialect::Dialect;
+use filter::RowFilter;
+use markup::{Markup, MarkupWriter};
+use sort::{RowDedup, RowSorter};
+use spreadsheet::{is_spreadsheet, sheet_reader, write_xlsx};
+use sql::{write_sql, write_sqlite};
+
+use crate::{get_decompressed_reader, get_writer};
+use csv::{Reader, StringRecord, Terminator, WriterBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{IsTerminal, Read, Write};
+
+use crate::{OutputFormat, SqlDialect};
+
+#[derive(Debug)]
+struct CsvRecord {
+    headers: Option<StringRecord>,
+    records: Vec<StringRecord>,
+}
+
+impl CsvRecord {
+    fn write_csv<W: Write>(
+        &self,
+        writer: W,
+        delimiter: char,
+        terminator: Terminator,
+    ) -> anyhow::Result<()> {
+        let mut wtr = WriterBuilder::new()
+            .delimiter(delimiter as u8)
+            .terminator(terminator)
+            .from_writer(writer);
+        if let Some(headers) = &self.headers {
+            wtr.write_record(headers)?;
+        }
+        for record in &self.records {
+            wtr.write_record(record)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+impl<R: Read> TryFrom<Reader<R>> for CsvRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(mut rdr: Reader<R>) -> Result<Self, Self::Error> {
+        let headers = read_headers(&mut rdr)?;
+        let records = rdr.records().collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { headers, records })
+    }
+}
+
+impl CsvRecord {
+    fn to_values(&self, types: &ColumnTypes) -> anyhow::Result<Vec<Value>> {
+        let converter = ValueConverter::for_record(types, self)?;
+        self.records
+            .iter()
+            .map(|record| converter.convert(self.headers.as_ref(), record))
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Toml {
+    items: Vec<Value>,
+}
+
+impl Toml {
+    fn new(items: Vec<Value>) -> Self {
+        Self { items }
+    }
+}
+
+/// How to read the input and shape the output of `process_csv`.
+#[derive(Debug, Clone)]
+pub struct CsvConfig {
+    /// The input delimiter, detected when `None`. CSV output uses it too, or `,`.
+    pub delimiter: Option<char>,
+    /// The input quote character, detected when `None`.
+    pub quote: Option<char>,
+    /// Whether the first row is a header, detected when `None`.
+    pub headers: Option<bool>,
+    /// An encoding label like `windows-1252` or `gbk`, detected when `None`.
+    pub encoding: Option<String>,
+    /// The sheet of an XLSX or ODS input, by name or 1-based position, the first when `None`.
+    pub sheet: Option<String>,
+    pub stream: bool,
+    pub types: ColumnTypes,
+    pub columns: ColumnSelection,
+    /// A `--where` expression rows have to match.
+    pub filter: Option<String>,
+    pub sort: Vec<SortKey>,
+    /// Key columns for dropping duplicate rows, empty to compare whole rows.
+    pub dedup: Option<Vec<String>>,
+    /// Rows sorted in memory by `--sort-by` or `--dedup` before spilling sorted runs to
+    /// temp files.
+    pub sort_buffer: usize,
+    /// Rows skipped after filtering, deduplicating and sorting.
+    pub offset: usize,
+    /// Rows kept after the offset.
+    pub limit: Option<usize>,
+    /// Keep only the last rows of what is left.
+    pub tail: Option<usize>,
+    /// Keep a random sample of this many rows of what is left.
+    pub sample: Option<usize>,
+    /// Seed for a reproducible `sample`.
+    pub seed: Option<u64>,
+    /// Widest cell of the raw table before it is cut, 0 for no limit.
+    pub max_width: usize,
+    /// Rows per Parquet row group or Arrow record batch.
+    pub row_group_size: usize,
+    pub sql_dialect: SqlDialect,
+    /// Table for SQL and SQLite output, `process_csv` defaults it to the input file name.
+    pub table: Option<String>,
+    /// Rows per SQL `INSERT` statement.
+    pub batch_size: usize,
+}
+
+impl Default for CsvConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            quote: None,
+            headers: None,
+            encoding: None,
+            sheet: None,
+            stream: false,
+            types: ColumnTypes::default(),
+            columns: ColumnSelection::default(),
+            filter: None,
+            sort: vec![],
+            dedup: None,
+            sort_buffer: 100_000,
+            offset: 0,
+            limit: None,
+            tail: None,
+            sample: None,
+            seed: None,
+            max_width: 40,
+            row_group_size: 65_536,
+            sql_dialect: SqlDialect::Sqlite,
+            table: None,
+            batch_size: 500,
+        }
+    }
+}
+
+impl CsvConfig {
+    fn output_delimiter(&self) -> char {
+        self.delimiter.unwrap_or(',')
+    }
+}
+
+/// Open `input`, a file or `-` for stdin, as UTF-8 CSV, decompressing it if needed and
+/// detecting whatever part of the dialect `config` leaves open, reported on stderr.
+/// Spreadsheets are read a sheet at a time as CSV.
+fn csv_reader(input: &str, config: &CsvConfig) -> anyhow::Result<Reader<Box<dyn Read>>> {
+    if is_spreadsheet(input) {
+        return sheet_reader(input, config);
+    }
+    let (dialect, reader) = Dialect::detect(get_decompressed_reader(input)?, config)?;
+    let given = [
+        config.delimiter.is_some(),
+        config.quote.is_some(),
+        config.headers.is_some(),
+        config.encoding.is_some(),
+    ];
+    if given.contains(&false) {
+        let name = if input == "-" { "stdin" } else { input };
+        eprintln!("Detected dialect of {}: {}", name, dialect);
+    }
+    Ok(dialect.reader_builder().from_reader(reader))
+}
+
+fn read_headers<R: Read>(rdr: &mut Reader<R>) -> anyhow::Result<Option<StringRecord>> {
+    if rdr.has_headers() {
+        Ok(Some(rdr.headers()?.clone()))
+    } else {
+        Ok(None)
+    }
+}
+
+type Rows<'a> = Box<dyn Iterator<Item = anyhow::Result<StringRecord>> + 'a>;
+
+/// The rows that pass the row-level options: filtered, deduplicated, sorted, then cut
+/// down by offset, limit, tail and sample, in that order.
+fn read_rows<'a, R: Read + 'a>(
+    rdr: Reader<R>,
+    headers: Option<&StringRecord>,
+    config: &CsvConfig,
+) -> anyhow::Result<Rows<'a>> {
+    let mut rows: Rows = Box::new(rdr.into_records().map(|record| Ok(record?)));
+
+    if let Some(filter) = &config.filter {
+        let filter = RowFilter::new(filter, headers)?;
+        rows = Box::new(rows.filter_map(move |record| {
+            match record.and_then(|r| filter.matches(&r).map(|keep| keep.then_some(r))) {
+                Ok(Some(record)) => Some(Ok(record)),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            }
+        }));
+    }
+
+    if let Some(columns) = &config.dedup {
+        rows = RowDedup::new(columns, headers, config.sort_buffer)?.dedup(rows)?;
+    }
+
+    if !config.sort.is_empty() {
+        rows = RowSorter::new(&config.sort, headers, config.sort_buffer)?.sort(rows)?;
+    }
+
+    if config.offset > 0 {
+        rows = Box::new(rows.skip(config.offset));
+    }
+    if let Some(limit) = config.limit {
+        rows = Box::new(rows.take(limit));
+    }
+    if let Some(n) = config.tail {
+        rows = sample::tail(rows, n)?;
+    }
+    if let Some(n) = config.sample {
+        rows = sample::sample(rows, n, config.seed)?;
+    }
+
+    Ok(rows)
+}
+
+fn read_csv(input: &str, config: &CsvConfig) -> anyhow::Result<CsvRecord> {
+    let mut rdr = csv_reader(input, config)?;
+    let headers = read_headers(&mut rdr)?;
+    let records = read_rows(rdr, headers.as_ref(), config)?.collect::<anyhow::Result<_>>()?;
+    CsvRecord { headers, records }.project(&config.columns)
+}
+
+fn csv_convert(
+    csv_record: CsvRecord,
+    format: OutputFormat,
+    config: &CsvConfig,
+) -> anyhow::Result<String> {
+    let types = &config.types;
+    match format {
+        OutputFormat::Raw => Ok(csv_record.to_table(config.max_width)),
+        OutputFormat::Csv => {
+            let mut buf = Vec::new();
+            csv_record.write_csv(&mut buf, config.output_delimiter(), Terminator::CRLF)?;
+            Ok(String::from_utf8(buf)?)
+        }
+        OutputFormat::Json => {
+            let contents = csv_record.to_values(types)?;
+            Ok(serde_json::to_string_pretty(&contents)?)
+        }
+        OutputFormat::Jsonl => {
+            let lines = csv_record
+                .to_values(types)?
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Yaml => {
+            let contents = csv_record.to_values(types)?;
+            Ok(serde_yaml::to_string(&contents)?)
+        }
+        OutputFormat::Toml => {
+            let contents = csv_record.to_values(types)?;
+            let contents = Toml::new(contents.into_iter().map(drop_nulls).collect());
+            Ok(toml::to_string(&contents)?)
+        }
+        OutputFormat::Markdown => csv_record.to_markup(Markup::Markdown),
+        OutputFormat::Html => csv_record.to_markup(Markup::Html),
+        OutputFormat::Xml => csv_record.to_markup(Markup::Xml),
+        OutputFormat::Sql => {
+            let mut buf = Vec::new();
+            write_sql(&csv_record, &mut buf, config)?;
+            buf.pop_if(|c| *c == b'\n');
+            Ok(String::from_utf8(buf)?)
+        }
+        OutputFormat::Parquet | OutputFormat::Arrow | OutputFormat::Sqlite | OutputFormat::Xlsx => {
+            Err(anyhow::anyhow!(
+                "{:?} output is binary and can't be rendered as text",
+                format
+            ))
+        }
+    }
+}
+
+/// Write text output to the file or stdout like `output_contents`, compressed when the
+/// file name ends in `.gz`, `.zst`, `.bz2` or `.xz`.
+fn write_contents(output: &str, contents: &str) -> anyhow::Result<()> {
+    if output == "-" {
+        println!("{}", contents);
+        return Ok(());
+    }
+    let mut writer = get_writer(output)?;
+    writer.write_all(contents.as_bytes())?;
+    writer.finish()
+}
+
+/// Convert and write to the output file or stdout, text formats through `write_contents`
+/// and the binary columnar ones through a writer.
+fn csv_output(
+    csv_record: CsvRecord,
+    output: &str,
+    format: OutputFormat,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Parquet | OutputFormat::Arrow | OutputFormat::Xlsx => {
+            if output == "-" && std::io::stdout().is_terminal() {
+                return Err(anyhow::anyhow!(
+                    "Not writing binary {:?} output to a terminal, pass --output or pipe it",
+                    format
+                ));
+            }
+            let mut writer = get_writer(output)?;
+            match format {
+                OutputFormat::Xlsx => write_xlsx(&csv_record, &mut writer, config)?,
+                format => write_columnar(csv_record, format, &mut writer, config)?,
+            }
+            writer.finish()?;
+        }
+        OutputFormat::Sqlite => write_sqlite(&csv_record, output, config)?,
+        // written as is, the last record ends in CRLF like the others
+        OutputFormat::Csv => {
+            let mut writer = get_writer(output)?;
+            csv_record.write_csv(&mut writer, config.output_delimiter(), Terminator::CRLF)?;
+            writer.finish()?;
+        }
+        format => write_contents(output, &csv_convert(csv_record, format, config)?)?,
+    }
+    Ok(())
+}
+
+/// Writes rows one at a time as they come off the reader.
+enum RowWriter<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Json(W),
+    Yaml(W),
+    Markup(MarkupWriter<W>),
+}
+
+impl<W: Write> RowWriter<W> {
+    fn new(
+        writer: W,
+        format: OutputFormat,
+        delimiter: char,
+        headers: Option<&StringRecord>,
+    ) -> anyhow::Result<Self> {
+        match format {
+            OutputFormat::Raw | OutputFormat::Csv => {
+                // raw can't be an aligned table while streaming, so it is plain CSV
+                let terminator = match format {
+                    OutputFormat::Csv => Terminator::CRLF,
+                    _ => Terminator::Any(b'\n'),
+                };
+                let mut wtr = WriterBuilder::new()
+                    .delimiter(delimiter as u8)
+                    .terminator(terminator)
+                    .from_writer(writer);
+                if let Some(headers) = headers {
+                    wtr.write_record(headers)?;
+                }
+                Ok(Self::Csv(Box::new(wtr)))
+            }
+            OutputFormat::Json | OutputFormat::Jsonl => Ok(Self::Json(writer)),
+            OutputFormat::Yaml => Ok(Self::Yaml(writer)),
+            OutputFormat::Markdown => Ok(Self::Markup(MarkupWriter::new(
+                writer,
+                Markup::Markdown,
+                headers,
+            )?)),
+            OutputFormat::Html => Ok(Self::Markup(MarkupWriter::new(
+                writer,
+                Markup::Html,
+                headers,
+            )?)),
+            OutputFormat::Xml => Ok(Self::Markup(MarkupWriter::new(
+                writer,
+                Markup::Xml,
+                headers,
+            )?)),
+            OutputFormat::Toml => Err(anyhow::anyhow!(
+                "TOML output can't be streamed, use another text format with --stream"
+            )),
+            OutputFormat::Parquet
+            | OutputFormat::Arrow
+            | OutputFormat::Sql
+            | OutputFormat::Sqlite
+            | OutputFormat::Xlsx => Err(anyhow::anyhow!(
+                "{:?} output can't be streamed, its schema is inferred from every row",
+                format
+            )),
+        }
+    }
+
+    fn write(
+        &mut self,
+        converter: &ValueConverter,
+        headers: Option<&StringRecord>,
+        record: &StringRecord,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Csv(wtr) => wtr.write_record(record)?,
+            Self::Json(writer) => {
+                serde_json::to_writer(&mut *writer, &converter.convert(headers, record)?)?;
+                writeln!(writer)?;
+            }
+            Self::Yaml(writer) => {
+                writeln!(writer, "---")?;
+                serde_yaml::to_writer(&mut *writer, &converter.convert(headers, record)?)?;
+            }
+            Self::Markup(wtr) => wtr.write(record)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> anyhow::Result<()> {
+        match self {
+            Self::Csv(mut wtr) => wtr.flush()?,
+            Self::Json(mut writer) | Self::Yaml(mut writer) => writer.flush()?,
+            Self::Markup(wtr) => {
+                wtr.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert row by row without holding the file in memory.
+///
+/// JSON is written as JSON Lines and YAML as a stream of `---` separated documents,
+/// since neither a single array nor a TOML table can be emitted before the last row is read.
+/// For the same reason raw writes CSV, as table column widths depend on every row.
+fn csv_stream<R: Read, W: Write>(
+    mut rdr: Reader<R>,
+    writer: W,
+    format: OutputFormat,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let headers = read_headers(&mut rdr)?;
+    let projection = Projection::new(&config.columns, headers.as_ref())?;
+    let rows = read_rows(rdr, headers.as_ref(), config)?;
+    let headers = projection.headers();
+    let converter = ValueConverter::for_stream(&config.types, headers)?;
+    let mut wtr = RowWriter::new(writer, format, config.output_delimiter(), headers)?;
+
+    for record in rows {
+        wtr.write(&converter, headers, &projection.apply(&record?))?;
+    }
+    wtr.finish()
+}
+
+pub fn process_csv(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    if config.stream {
+        let rdr = csv_reader(input, config)?;
+        let mut writer = get_writer(output)?;
+        csv_stream(rdr, &mut writer, format, config)?;
+        return writer.finish();
+    }
+
+    let csv_record: CsvRecord = read_csv(input, config)?;
+    let table = config.table.clone().or_else(|| table_name(input));
+    csv_output(
+        csv_record,
+        output,
+        format,
+        &CsvConfig {
+            table,
+            ..config.clone()
+        },
+    )
+}
+
+/// A table name from the input file name, `players` for `players.csv.gz`.
+fn table_name(input: &str) -> Option<String> {
+    let name = std::path::Path::new(input).file_name()?.to_str()?;
+    let stem = name.split('.').next().filter(|stem| !stem.is_empty())?;
+    (input != "-").then(|| stem.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvType;
+    use csv::ReaderBuilder;
+
+    const DATA: &str = "Name,Kit Number\nSzczesny,1\nPerin,37\n";
+
+    fn reader(data: &str, no_header: bool) -> Reader<&[u8]> {
+        ReaderBuilder::new()
+            .has_headers(!no_header)
+            .from_reader(data.as_bytes())
+    }
+
+    fn stream(data: &str, format: OutputFormat, no_header: bool) -> anyhow::Result<String> {
+        let mut buf = Vec::new();
+        csv_stream(
+            reader(data, no_header),
+            &mut buf,
+            format,
+            &CsvConfig::default(),
+        )?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    #[test]
+    fn test_csv_convert_json() {
+        let csv_record: CsvRecord = reader(DATA, false).try_into().unwrap();
+        let contents = csv_convert(csv_record, OutputFormat::Json, &CsvConfig::default());
+        let values: Vec<Value> = serde_json::from_str(&contents.unwrap()).unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[1]["Name"], "Perin");
+        assert_eq!(values[1]["Kit Number"], "37");
+    }
+
+    #[test]
+    fn test_csv_convert_infer_types() {
+        let data = "Name,Kit Number,Active\nSzczesny,1,true\nPerin,,false\n";
+        let csv_record: CsvRecord = reader(data, false).try_into().unwrap();
+        let types = ColumnTypes {
+            infer: true,
+            columns: vec![],
+            nested: false,
+        };
+        let config = CsvConfig {
+            types,
+            ..Default::default()
+        };
+        let contents = csv_convert(csv_record, OutputFormat::Toml, &config).unwrap();
+        assert_eq!(
+            contents,
+            "[[items]]\nName = \"Szczesny\"\n\"Kit Number\" = 1\nActive = true\n\n\
+             [[items]]\nName = \"Perin\"\nActive = false\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_convert_forced_type() {
+        let csv_record: CsvRecord = reader(DATA, true).try_into().unwrap();
+        let types = ColumnTypes {
+            infer: false,
+            columns: vec![("1".to_string(), CsvType::Integer)],
+            nested: false,
+        };
+        let config = CsvConfig {
+            types,
+            ..Default::default()
+        };
+        assert!(csv_convert(csv_record, OutputFormat::Json, &config).is_err());
+    }
+
+    #[test]
+    fn test_csv_convert_csv_quotes_and_delimiter() {
+        let csv_record: CsvRecord = reader("a,b\n\"x;y\",\"1,2\"\n", false).try_into().unwrap();
+        let config = CsvConfig {
+            delimiter: Some(';'),
+            ..Default::default()
+        };
+        let contents = csv_convert(csv_record, OutputFormat::Csv, &config).unwrap();
+        assert_eq!(contents, "a;b\r\n\"x;y\";1,2\r\n");
+    }
+
+    #[test]
+    fn test_csv_convert_nested() {
+        let data = "name,address.city,tags[0],tags[1]\nPerin,Turin,gk,\n";
+        let csv_record: CsvRecord = reader(data, false).try_into().unwrap();
+        let config = CsvConfig {
+            types: ColumnTypes {
+                infer: true,
+                columns: vec![],
+                nested: true,
+            },
+            ..Default::default()
+        };
+        let contents = csv_convert(csv_record, OutputFormat::Jsonl, &config).unwrap();
+        assert_eq!(
+            contents,
+            r#"{"name":"Perin","address":{"city":"Turin"},"tags":["gk",""]}"#
+        );
+    }
+
+    #[test]
+    fn test_csv_no_header_keeps_first_row() {
+        let csv_record: CsvRecord = reader(DATA, true).try_into().unwrap();
+        assert!(csv_record.headers.is_none());
+        assert_eq!(csv_record.records.len(), 3);
+    }
+
+    #[test]
+    fn test_csv_stream_json_lines() {
+        let output = stream(DATA, OutputFormat::Json, false).unwrap();
+        assert_eq!(
+            output,
+            "{\"Name\":\"Szczesny\",\"Kit Number\":\"1\"}\n{\"Name\":\"Perin\",\"Kit Number\":\"37\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_stream_yaml_documents() {
+        let output = stream(DATA, OutputFormat::Yaml, true).unwrap();
+        assert_eq!(output.matches("---\n").count(), 3);
+        assert!(output.contains("- Perin\n- '37'\n"));
+    }
+
+    #[test]
+    fn test_csv_stream_raw() {
+        let output = stream("a,b\n\"x,y\",1\n", OutputFormat::Raw, false).unwrap();
+        assert_eq!(output, "a,b\n\"x,y\",1\n");
+    }
+
+    #[test]
+    fn test_csv_reader_compressed_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        for ext in ["gz", "zst", "bz2", "xz"] {
+            let path = dir.path().join(format!("players.csv.{}", ext));
+            let path = path.to_str().unwrap();
+            write_contents(path, DATA).unwrap();
+            assert!(!std::fs::read(path).unwrap().starts_with(b"Name"));
+            let csv_record: CsvRecord = csv_reader(path, &CsvConfig::default())
+                .unwrap()
+                .try_into()
+                .unwrap();
+            assert_eq!(csv_record.records.len(), 2, "{}", ext);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_compressed_output_reports_failed_finish() {
+        let dir = tempfile::tempdir().unwrap();
+        for ext in ["gz", "zst", "bz2", "xz"] {
+            // every write to /dev/full fails with "No space left on device"
+            let path = dir.path().join(format!("full.csv.{}", ext));
+            std::os::unix::fs::symlink("/dev/full", &path).unwrap();
+            assert!(
+                write_contents(path.to_str().unwrap(), DATA).is_err(),
+                "{}",
+                ext
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_convert_jsonl_without_header() {
+        let csv_record: CsvRecord = reader(DATA, true).try_into().unwrap();
+        let contents = csv_convert(csv_record, OutputFormat::Jsonl, &CsvConfig::default());
+        assert_eq!(
+            contents.unwrap(),
+            "[\"Name\",\"Kit Number\"]\n[\"Szczesny\",\"1\"]\n[\"Perin\",\"37\"]"
+        );
+    }
+
+    #[test]
+    fn test_csv_stream_html() {
+        let output = stream(DATA, OutputFormat::Html, false).unwrap();
+        assert!(output.starts_with("<table>\n  <thead>\n    <tr><th>Name</th>"));
+        assert!(output.ends_with("<td>Perin</td><td>37</td></tr>\n  </tbody>\n</table>\n"));
+    }
+
+    #[test]
+    fn test_csv_stream_toml_unsupported() {
+        assert!(stream(DATA, OutputFormat::Toml, false).is_err());
+    }
+}
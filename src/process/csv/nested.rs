@@ -0,0 +1,221 @@
+use csv::StringRecord;
+use serde_json::{Map, Value};
+
+/// Largest array index a header may use. Lower ones are padded with nulls, like
+/// `tags[3]` alone, but every row allocates an array that long.
+const MAX_INDEX: usize = 10_000;
+
+/// One step of a header path, `address.city` is two keys, `tags[0]` a key and an index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Header paths for building nested objects and arrays out of flat rows.
+#[derive(Debug)]
+pub(super) struct Nesting {
+    paths: Vec<Vec<Segment>>,
+}
+
+impl Nesting {
+    /// Parse every header and reject ones that would overwrite each other, like `a` and
+    /// `a.b`, so rows can be nested without further checks.
+    ///
+    /// Array indices up to `MAX_INDEX` are allowed and may leave gaps, which nest as nulls.
+    pub(super) fn new(headers: &StringRecord) -> anyhow::Result<Self> {
+        let paths: Vec<_> = headers.iter().map(parse_path).collect();
+        for (header, path) in headers.iter().zip(&paths) {
+            let too_large = path.iter().find_map(|segment| match segment {
+                Segment::Index(i) if *i > MAX_INDEX => Some(*i),
+                _ => None,
+            });
+            if let Some(index) = too_large {
+                return Err(anyhow::anyhow!(
+                    "Header `{}` has array index {}, more than {}, can't nest it",
+                    header,
+                    index,
+                    MAX_INDEX
+                ));
+            }
+        }
+        let mut probe = Value::Null;
+        for (header, path) in headers.iter().zip(&paths) {
+            if !insert(&mut probe, path, Value::Bool(true)) {
+                return Err(anyhow::anyhow!(
+                    "Header `{}` conflicts with another header, can't nest it",
+                    header
+                ));
+            }
+        }
+        Ok(Self { paths })
+    }
+
+    pub(super) fn nest(&self, cells: impl Iterator<Item = Value>) -> Value {
+        let mut root = Value::Object(Map::new());
+        for (path, cell) in self.paths.iter().zip(cells) {
+            insert(&mut root, path, cell);
+        }
+        root
+    }
+}
+
+/// Split `a.b[0].c` into segments; a header that isn't a clean path stays one key.
+fn parse_path(header: &str) -> Vec<Segment> {
+    let mut path = Vec::new();
+    for part in header.split('.') {
+        let (key, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if key.is_empty() {
+            return vec![Segment::Key(header.to_string())];
+        }
+        path.push(Segment::Key(key.to_string()));
+        while let Some(rest) = indices.strip_prefix('[') {
+            let Some((index, rest)) = rest.split_once(']') else {
+                return vec![Segment::Key(header.to_string())];
+            };
+            let Ok(index) = index.parse() else {
+                return vec![Segment::Key(header.to_string())];
+            };
+            path.push(Segment::Index(index));
+            indices = rest;
+        }
+        if !indices.is_empty() {
+            return vec![Segment::Key(header.to_string())];
+        }
+    }
+    path
+}
+
+/// Set the value at `path`, creating objects and arrays on the way. Returns false when
+/// the path runs into a value of another shape or one that is already set.
+fn insert(node: &mut Value, path: &[Segment], value: Value) -> bool {
+    let Some((segment, rest)) = path.split_first() else {
+        if !node.is_null() {
+            return false;
+        }
+        *node = value;
+        return true;
+    };
+    if node.is_null() {
+        *node = match segment {
+            Segment::Key(_) => Value::Object(Map::new()),
+            Segment::Index(_) => Value::Array(Vec::new()),
+        };
+    }
+    let child = match (segment, node) {
+        (Segment::Key(key), Value::Object(map)) => map.entry(key.clone()).or_insert(Value::Null),
+        (Segment::Index(index), Value::Array(items)) => {
+            if items.len() <= *index {
+                items.resize(index + 1, Value::Null);
+            }
+            &mut items[*index]
+        }
+        _ => return false,
+    };
+    insert(child, rest, value)
+}
+
+/// Flatten nested objects and arrays into `a.b` and `tags[0]` keys, the reverse of nesting.
+pub(super) fn flatten(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut flat = Map::new();
+            for (key, value) in map {
+                flatten_into(&mut flat, key, value);
+            }
+            Value::Object(flat)
+        }
+        value => value,
+    }
+}
+
+fn flatten_into(flat: &mut Map<String, Value>, prefix: String, value: Value) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                flatten_into(flat, format!("{}.{}", prefix, key), value);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, value) in items.into_iter().enumerate() {
+                flatten_into(flat, format!("{}[{}]", prefix, i), value);
+            }
+        }
+        value => {
+            flat.insert(prefix, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("a.tags[1].b"),
+            vec![
+                Segment::Key("a".into()),
+                Segment::Key("tags".into()),
+                Segment::Index(1),
+                Segment::Key("b".into())
+            ]
+        );
+        assert_eq!(
+            parse_path("Kit Number"),
+            vec![Segment::Key("Kit Number".into())]
+        );
+        assert_eq!(parse_path("a[x]"), vec![Segment::Key("a[x]".into())]);
+        assert_eq!(parse_path(".5"), vec![Segment::Key(".5".into())]);
+    }
+
+    #[test]
+    fn test_nest_row() {
+        let headers = StringRecord::from(vec!["name", "address.city", "tags[0]", "tags[1]"]);
+        let nesting = Nesting::new(&headers).unwrap();
+        let row = ["Perin", "Turin", "gk", "it"].map(Value::from);
+        assert_eq!(
+            nesting.nest(row.into_iter()),
+            json!({"name": "Perin", "address": {"city": "Turin"}, "tags": ["gk", "it"]})
+        );
+    }
+
+    #[test]
+    fn test_nest_conflicting_headers() {
+        for headers in [vec!["a", "a.b"], vec!["a.b", "a[0]"], vec!["a", "a"]] {
+            assert!(Nesting::new(&StringRecord::from(headers)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_nest_index_bound_and_gaps() {
+        let headers = StringRecord::from(vec!["name", "x[9999999999999]"]);
+        let err = Nesting::new(&headers).unwrap_err();
+        assert!(err.to_string().contains("`x[9999999999999]`"));
+        assert!(Nesting::new(&StringRecord::from(vec!["x[10001]"])).is_err());
+
+        // sparse and 1-based indices leave nulls in the gaps
+        let nesting = Nesting::new(&StringRecord::from(vec!["id", "tags[3]", "x[1]"])).unwrap();
+        let row = ["7", "gk", "a"].map(Value::from);
+        assert_eq!(
+            nesting.nest(row.into_iter()),
+            json!({"id": "7", "tags": [null, null, null, "gk"], "x": [null, "a"]})
+        );
+    }
+
+    #[test]
+    fn test_flatten_roundtrip() {
+        let value = json!({"name": "Perin", "address": {"city": "Turin"}, "tags": ["gk"], "x": []});
+        let Value::Object(flat) = flatten(value.clone()) else {
+            panic!("flattened row is an object")
+        };
+        assert_eq!(
+            flat.keys().collect::<Vec<_>>(),
+            vec!["name", "address.city", "tags[0]", "x"]
+        );
+        let nesting = Nesting::new(&flat.keys().collect()).unwrap();
+        assert_eq!(nesting.nest(flat.into_iter().map(|(_, v)| v)), value);
+    }
+}
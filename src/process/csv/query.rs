@@ -0,0 +1,124 @@
+use super::{
+    csv_output, read_csv,
+    sql::{load_table, quote_identifier},
+    table_name, ColumnTypes, CsvConfig, CsvRecord,
+};
+use crate::{OutputFormat, SqlDialect};
+use csv::StringRecord;
+use rusqlite::{types::ValueRef, Connection};
+use std::collections::HashSet;
+
+/// Name the first input can always be queried by.
+const FIRST_TABLE: &str = "t";
+
+/// Load every input into an in-memory SQLite database and run `sql` against it.
+///
+/// Each input is a table named after its file, `players` for `players.csv.gz`; the
+/// first one can also be queried as `t`.
+fn csv_query(inputs: &[String], sql: &str, config: &CsvConfig) -> anyhow::Result<CsvRecord> {
+    let conn = Connection::open_in_memory()?;
+    let mut names = HashSet::new();
+    for (i, input) in inputs.iter().enumerate() {
+        let name = table_name(input).unwrap_or_else(|| "stdin".to_string());
+        if !names.insert(name.clone()) {
+            return Err(anyhow::anyhow!(
+                "Table `{}` is already taken by another input, rename the file",
+                name
+            ));
+        }
+        load_table(&conn, &read_csv(input, config)?, &name, config)?;
+        if i == 0 && name != FIRST_TABLE {
+            conn.execute(
+                &format!(
+                    "CREATE VIEW {} AS SELECT * FROM {}",
+                    FIRST_TABLE,
+                    quote_identifier(SqlDialect::Sqlite, &name)
+                ),
+                [],
+            )?;
+            names.insert(FIRST_TABLE.to_string());
+        }
+    }
+
+    let mut stmt = conn.prepare(sql)?;
+    let headers: StringRecord = stmt.column_names().into_iter().collect();
+    let width = headers.len();
+    let mut rows = stmt.query([])?;
+    let mut records = Vec::new();
+    while let Some(row) = rows.next()? {
+        let record = (0..width)
+            .map(|i| Ok(cell(row.get_ref(i)?)))
+            .collect::<rusqlite::Result<StringRecord>>()?;
+        records.push(record);
+    }
+    Ok(CsvRecord {
+        headers: Some(headers),
+        records,
+    })
+}
+
+fn cell(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(text) | ValueRef::Blob(text) => String::from_utf8_lossy(text).into_owned(),
+    }
+}
+
+pub fn process_csv_query(
+    inputs: &[String],
+    output: &str,
+    format: OutputFormat,
+    sql: &str,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let result = csv_query(inputs, sql, config)?;
+    let config = CsvConfig {
+        types: ColumnTypes {
+            infer: true,
+            columns: vec![],
+            nested: false,
+        },
+        ..config.clone()
+    };
+    csv_output(result, output, format, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_group_by() {
+        let input = "assets/juventus.csv".to_string();
+        let sql = "SELECT Nationality, count(*) AS players FROM t \
+                   GROUP BY 1 ORDER BY 2 DESC, 1 LIMIT 1";
+        let result = csv_query(&[input], sql, &CsvConfig::default()).unwrap();
+        assert_eq!(result.headers.unwrap(), vec!["Nationality", "players"]);
+        assert_eq!(result.records[0], vec!["Italy", "8"]);
+    }
+
+    #[test]
+    fn test_query_joins_inputs_by_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let inputs = [
+            path("players.csv", "id,name\n1,Perin\n2,Buffon\n"),
+            path("kits.csv", "id,kit\n1,37\n2,1\n"),
+        ];
+        let sql = "SELECT name, kit + 1 AS next FROM t JOIN kits USING (id) ORDER BY kit";
+        let result = csv_query(&inputs, sql, &CsvConfig::default()).unwrap();
+        assert_eq!(
+            result.records,
+            vec![vec!["Buffon", "2"], vec!["Perin", "38"]]
+        );
+
+        let inputs = [inputs[0].clone(), inputs[0].clone()];
+        assert!(csv_query(&inputs, "SELECT 1", &CsvConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use super::Rows;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+
+/// Rows allocated up front, the buffers grow from there so a huge `n` costs nothing
+/// until that many rows are read.
+const INITIAL_CAPACITY: usize = 1024;
+
+/// The last `n` rows, keeping only `n` in memory while reading.
+pub(super) fn tail<'a>(rows: Rows<'a>, n: usize) -> anyhow::Result<Rows<'a>> {
+    let mut last = VecDeque::with_capacity(n.min(INITIAL_CAPACITY));
+    for record in rows {
+        let record = record?;
+        if last.len() == n {
+            last.pop_front();
+        }
+        if n > 0 {
+            last.push_back(record);
+        }
+    }
+    Ok(Box::new(last.into_iter().map(Ok)))
+}
+
+/// A uniform random sample of `n` rows in one pass, by reservoir sampling, kept in input
+/// order. The same `seed` picks the same rows across rand versions and platforms:
+/// ChaCha8 is used by name and indices are drawn from its raw output, not `gen_range`.
+pub(super) fn sample<'a>(rows: Rows<'a>, n: usize, seed: Option<u64>) -> anyhow::Result<Rows<'a>> {
+    let mut rng = match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let mut reservoir = Vec::with_capacity(n.min(INITIAL_CAPACITY));
+    for (i, record) in rows.enumerate() {
+        let record = record?;
+        if reservoir.len() < n {
+            reservoir.push((i, record));
+        } else {
+            // multiply-shift into 0..=i, the bias is at most i / 2^64
+            let j = ((rng.next_u64() as u128 * (i as u128 + 1)) >> 64) as usize;
+            if j < n {
+                reservoir[j] = (i, record);
+            }
+        }
+    }
+    reservoir.sort_by_key(|(i, _)| *i);
+    Ok(Box::new(
+        reservoir.into_iter().map(|(_, record)| Ok(record)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::StringRecord;
+
+    fn rows(n: usize) -> Rows<'static> {
+        Box::new((0..n).map(|i| Ok(StringRecord::from(vec![i.to_string()]))))
+    }
+
+    fn cells(rows: Rows) -> Vec<String> {
+        rows.map(|r| r.unwrap()[0].to_string()).collect()
+    }
+
+    #[test]
+    fn test_tail() {
+        assert_eq!(cells(tail(rows(5), 2).unwrap()), vec!["3", "4"]);
+        assert_eq!(cells(tail(rows(1), 3).unwrap()), vec!["0"]);
+        assert!(cells(tail(rows(3), 0).unwrap()).is_empty());
+        assert_eq!(cells(tail(rows(2), usize::MAX).unwrap()), vec!["0", "1"]);
+    }
+
+    #[test]
+    fn test_sample_is_seeded_and_ordered() {
+        let first = cells(sample(rows(1_000), 10, Some(7)).unwrap());
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, cells(sample(rows(1_000), 10, Some(7)).unwrap()));
+        assert_ne!(first, cells(sample(rows(1_000), 10, Some(8)).unwrap()));
+        let numbers: Vec<usize> = first.iter().map(|c| c.parse().unwrap()).collect();
+        assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(cells(sample(rows(3), 10, Some(7)).unwrap()).len(), 3);
+        assert_eq!(
+            cells(sample(rows(3), usize::MAX, Some(7)).unwrap()).len(),
+            3
+        );
+        // pinned, a seed has to pick the same rows after dependency upgrades
+        assert_eq!(
+            cells(sample(rows(20), 3, Some(7)).unwrap()),
+            vec!["1", "9", "13"]
+        );
+    }
+}
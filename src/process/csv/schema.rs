@@ -0,0 +1,326 @@
+use super::{csv_reader, read_headers, CsvConfig};
+use crate::{get_content, get_writer, CsvType};
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{cmp::Ordering, collections::HashSet, io::Write};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CsvSchema {
+    pub columns: Vec<ColumnSchema>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: CsvType,
+    pub nullable: bool,
+    pub unique: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+}
+
+/// Running facts about one column while the file is scanned.
+#[derive(Debug, Default)]
+struct ColumnProfile {
+    ty: Option<CsvType>,
+    nullable: bool,
+    unique: bool,
+    seen: HashSet<String>,
+    min: Option<String>,
+    max: Option<String>,
+}
+
+impl ColumnProfile {
+    fn new() -> Self {
+        Self {
+            unique: true,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, cell: &str) {
+        if cell.is_empty() {
+            self.nullable = true;
+            return;
+        }
+        let ty = CsvType::of(cell);
+        self.ty = Some(self.ty.map_or(ty, |t| t.merge(ty)));
+        if self.unique && !self.seen.insert(cell.to_string()) {
+            self.unique = false;
+            self.seen.clear();
+        }
+        // both bounds are kept as strings and compared in the final type
+        for (bound, keep) in [
+            (&mut self.min, Ordering::Less),
+            (&mut self.max, Ordering::Greater),
+        ] {
+            match bound {
+                Some(current) if compare_loose(cell, current) != keep => {}
+                _ => *bound = Some(cell.to_string()),
+            }
+        }
+    }
+
+    fn into_schema(self, name: String) -> anyhow::Result<ColumnSchema> {
+        let ty = self.ty.unwrap_or(CsvType::String);
+        let bound = |cell: Option<String>| match ty {
+            CsvType::Integer | CsvType::Float | CsvType::Date => {
+                cell.map(|cell| ty.convert(&cell)).transpose()
+            }
+            CsvType::String | CsvType::Boolean => Ok(None),
+        };
+        Ok(ColumnSchema {
+            name,
+            ty,
+            nullable: self.nullable,
+            unique: self.unique,
+            min: bound(self.min)?,
+            max: bound(self.max)?,
+        })
+    }
+}
+
+/// Numbers compare by value, everything else, ISO dates included, by text.
+fn compare_loose(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+fn column_names(headers: Option<&StringRecord>, width: usize) -> Vec<String> {
+    match headers {
+        Some(headers) => headers.iter().map(str::to_string).collect(),
+        None => (0..width).map(|i| i.to_string()).collect(),
+    }
+}
+
+fn infer_schema(input: &str, config: &CsvConfig) -> anyhow::Result<CsvSchema> {
+    let mut rdr = csv_reader(input, config)?;
+    let headers = read_headers(&mut rdr)?;
+    let mut profiles: Vec<ColumnProfile> = Vec::new();
+
+    for record in rdr.into_records() {
+        let record = record?;
+        let width = record.len().max(headers.as_ref().map_or(0, |h| h.len()));
+        profiles.resize_with(width.max(profiles.len()), ColumnProfile::new);
+        for (i, profile) in profiles.iter_mut().enumerate() {
+            profile.add(record.get(i).unwrap_or_default());
+        }
+    }
+
+    let names = column_names(headers.as_ref(), profiles.len());
+    profiles.resize_with(names.len(), ColumnProfile::new);
+    let columns = names
+        .into_iter()
+        .zip(profiles)
+        .map(|(name, profile)| profile.into_schema(name))
+        .collect::<anyhow::Result<_>>()?;
+    Ok(CsvSchema { columns })
+}
+
+impl ColumnSchema {
+    fn check(&self, cell: &str, seen: &mut HashSet<String>) -> Option<String> {
+        if cell.is_empty() {
+            return (!self.nullable).then(|| "is empty but the column is not nullable".into());
+        }
+        let value = match self.ty.convert(cell) {
+            Ok(value) => value,
+            Err(e) => return Some(e.to_string()),
+        };
+        if self.unique && !seen.insert(cell.to_string()) {
+            return Some(format!("`{}` is a duplicate in a unique column", cell));
+        }
+        let out_of_range = |bound: &Option<Value>, reject: Ordering| {
+            bound
+                .as_ref()
+                .is_some_and(|bound| compare_values(&value, bound) == Some(reject))
+        };
+        if out_of_range(&self.min, Ordering::Less) {
+            return Some(format!(
+                "`{}` is below the minimum {}",
+                cell,
+                self.min.as_ref()?
+            ));
+        }
+        if out_of_range(&self.max, Ordering::Greater) {
+            return Some(format!(
+                "`{}` is above the maximum {}",
+                cell,
+                self.max.as_ref()?
+            ));
+        }
+        None
+    }
+}
+
+fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Check every row against the schema and write one line per problem found.
+fn validate<W: Write>(
+    input: &str,
+    schema: &CsvSchema,
+    config: &CsvConfig,
+    mut writer: W,
+) -> anyhow::Result<usize> {
+    let mut rdr = csv_reader(input, config)?;
+    let headers = read_headers(&mut rdr)?;
+    let mut errors = 0;
+
+    if let Some(headers) = &headers {
+        let expected = schema.columns.iter().map(|c| c.name.as_str());
+        if !headers.iter().eq(expected) {
+            writeln!(
+                writer,
+                "line 1: expected columns {:?}, found {:?}",
+                schema.columns.iter().map(|c| &c.name).collect::<Vec<_>>(),
+                headers.iter().collect::<Vec<_>>()
+            )?;
+            writer.flush()?;
+            return Ok(1);
+        }
+    }
+
+    let mut seen = vec![HashSet::new(); schema.columns.len()];
+    for record in rdr.into_records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => match e.kind() {
+                csv::ErrorKind::UnequalLengths {
+                    pos,
+                    expected_len,
+                    len,
+                } => {
+                    writeln!(
+                        writer,
+                        "line {}: expected {} fields, found {}",
+                        pos.as_ref().map_or(0, |p| p.line()),
+                        expected_len,
+                        len
+                    )?;
+                    errors += 1;
+                    continue;
+                }
+                _ => return Err(e.into()),
+            },
+        };
+        let line = record.position().map_or(0, |p| p.line());
+        if record.len() != schema.columns.len() {
+            writeln!(
+                writer,
+                "line {}: expected {} fields, found {}",
+                line,
+                schema.columns.len(),
+                record.len()
+            )?;
+            errors += 1;
+            continue;
+        }
+        for ((column, cell), seen) in schema.columns.iter().zip(record.iter()).zip(&mut seen) {
+            if let Some(problem) = column.check(cell, seen) {
+                writeln!(
+                    writer,
+                    "line {}, column `{}`: {}",
+                    line, column.name, problem
+                )?;
+                errors += 1;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(errors)
+}
+
+pub fn process_csv_schema(input: &str, output: &str, config: &CsvConfig) -> anyhow::Result<()> {
+    let schema = infer_schema(input, config)?;
+    let mut writer = get_writer(output)?;
+    serde_json::to_writer_pretty(&mut writer, &schema)?;
+    writeln!(writer)?;
+    writer.finish()
+}
+
+/// Fails when any row breaks the schema, so pipelines can stop on a bad file.
+pub fn process_csv_validate(
+    input: &str,
+    schema: &str,
+    output: &str,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let schema: CsvSchema = serde_json::from_str(&get_content(schema)?)?;
+    let mut writer = get_writer(output)?;
+    let errors = validate(input, &schema, config, &mut writer)?;
+    writer.finish()?;
+    match errors {
+        0 => Ok(()),
+        errors => Err(anyhow::anyhow!(
+            "{} failed validation with {} error(s)",
+            input,
+            errors
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_input(dir: &tempfile::TempDir, name: &str, contents: &str) -> String {
+        let path = dir.path().join(format!("{}.csv", name));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_infer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_input(
+            &dir,
+            "infer",
+            "Name,Kit Number,Joined\nPerin,37,2018-07-01\nBuffon,,2019-07-04\n",
+        );
+        let schema = infer_schema(&input, &CsvConfig::default()).unwrap();
+        let kit = &schema.columns[1];
+        assert_eq!(kit.ty, CsvType::Integer);
+        assert!(kit.nullable && kit.unique);
+        assert_eq!(kit.min, Some(Value::from(37)));
+        let joined = &schema.columns[2];
+        assert_eq!(joined.ty, CsvType::Date);
+        assert_eq!(joined.max, Some(Value::from("2019-07-04")));
+        assert!(schema.columns[0].min.is_none());
+    }
+
+    #[test]
+    fn test_validate_reports_each_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = infer_schema(
+            &write_input(&dir, "valid", "Name,Kit Number\nPerin,37\nBuffon,77\n"),
+            &CsvConfig::default(),
+        )
+        .unwrap();
+        let input = write_input(
+            &dir,
+            "invalid",
+            "Name,Kit Number\nPerin,x\n,99\nPerin,40\nPjaca\n",
+        );
+        let mut report = Vec::new();
+        let errors = validate(&input, &schema, &CsvConfig::default(), &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert_eq!(errors, 5);
+        assert!(report.contains("line 2, column `Kit Number`: `x` is not an integer"));
+        assert!(report.contains("line 3, column `Name`: is empty"));
+        assert!(report.contains("line 3, column `Kit Number`: `99` is above the maximum 77"));
+        assert!(report.contains("line 4, column `Name`: `Perin` is a duplicate"));
+        assert!(report.contains("line 5: expected 2 fields, found 1"));
+    }
+}
@@ -0,0 +1,401 @@
+use super::{column_index, Rows};
+use csv::{Position, ReaderBuilder, StringRecord, StringRecordsIntoIter, WriterBuilder};
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+use tempfile::{NamedTempFile, TempPath};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+}
+
+/// Runs merged at once. With more, groups of runs are merged into new runs first, so
+/// the number of open temp files stays bounded.
+const MERGE_FAN_IN: usize = 64;
+
+/// Sorts rows by a list of keys, spilling to temp files once the buffer is full.
+#[derive(Debug, Clone)]
+pub(super) struct RowSorter {
+    order: Order,
+    buffer: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Order {
+    /// `--sort-by` columns, each ascending or descending.
+    Keys(Vec<(usize, bool)>),
+    /// Cells as plain bytes, of the given columns or the whole row, so only equal
+    /// cells compare equal.
+    Exact(Option<Vec<usize>>),
+    /// Line number in the input.
+    Line,
+}
+
+impl RowSorter {
+    pub(super) fn new(
+        keys: &[SortKey],
+        headers: Option<&StringRecord>,
+        buffer: usize,
+    ) -> anyhow::Result<Self> {
+        let keys = keys
+            .iter()
+            .map(|key| {
+                let index = column_index(headers, &key.column)
+                    .map_err(|e| anyhow::anyhow!("{} in --sort-by", e))?;
+                Ok((index, key.descending))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            order: Order::Keys(keys),
+            buffer: buffer.max(1),
+        })
+    }
+
+    fn compare(&self, a: &StringRecord, b: &StringRecord) -> Ordering {
+        match &self.order {
+            Order::Keys(keys) => keys
+                .iter()
+                .map(|&(i, descending)| {
+                    let ordering =
+                        compare_cells(a.get(i).unwrap_or_default(), b.get(i).unwrap_or_default());
+                    if descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal),
+            Order::Exact(Some(columns)) => columns
+                .iter()
+                .map(|&i| {
+                    a.get(i)
+                        .unwrap_or_default()
+                        .cmp(b.get(i).unwrap_or_default())
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal),
+            Order::Exact(None) => a.iter().cmp(b.iter()),
+            Order::Line => line(a).cmp(&line(b)),
+        }
+    }
+
+    /// Sort the rows, stable, in memory when they fit in the buffer and with an
+    /// external merge sort otherwise.
+    pub(super) fn sort<'a>(self, mut rows: Rows<'a>) -> anyhow::Result<Rows<'a>> {
+        let mut runs = Vec::new();
+        loop {
+            let mut chunk = rows
+                .by_ref()
+                .take(self.buffer)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            chunk.sort_by(|a, b| self.compare(a, b));
+
+            let full = chunk.len() == self.buffer;
+            if runs.is_empty() && !full {
+                return Ok(Box::new(chunk.into_iter().map(Ok)));
+            }
+            if !chunk.is_empty() {
+                runs.push(spill(chunk.into_iter().map(Ok))?);
+            }
+            if !full {
+                break;
+            }
+        }
+
+        // merge consecutive runs so ties keep their order and the sort stays stable
+        while runs.len() > MERGE_FAN_IN {
+            let mut rest = runs.into_iter();
+            runs = Vec::new();
+            loop {
+                let group: Vec<TempPath> = rest.by_ref().take(MERGE_FAN_IN).collect();
+                if group.is_empty() {
+                    break;
+                }
+                runs.push(spill(self.merge(group)?)?);
+            }
+        }
+        Ok(Box::new(self.merge(runs)?))
+    }
+
+    fn merge(&self, paths: Vec<TempPath>) -> anyhow::Result<Merge> {
+        let mut runs = paths
+            .iter()
+            .map(|path| {
+                Ok(ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .from_reader(BufReader::new(File::open(path)?))
+                    .into_records())
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let heads = runs
+            .iter_mut()
+            .map(next_spilled)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Merge {
+            sorter: self.clone(),
+            runs,
+            heads,
+            _paths: paths,
+        })
+    }
+}
+
+/// Numbers sort by value and before text, text sorts by its bytes.
+pub(super) fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// Write a sorted run to a temp file, each row followed by its original line number so
+/// error messages still point into the input. The file is closed until it is merged.
+fn spill(rows: impl Iterator<Item = anyhow::Result<StringRecord>>) -> anyhow::Result<TempPath> {
+    let (file, path) = NamedTempFile::new()?.into_parts();
+    let mut wtr = WriterBuilder::new()
+        .flexible(true)
+        .from_writer(BufWriter::new(file));
+    for record in rows {
+        let record = record?;
+        wtr.write_record(record.iter().chain([line(&record).to_string().as_str()]))?;
+    }
+    wtr.into_inner()?.into_inner()?;
+    Ok(path)
+}
+
+fn line(record: &StringRecord) -> u64 {
+    record.position().map_or(0, |p| p.line())
+}
+
+fn next_spilled(
+    run: &mut StringRecordsIntoIter<BufReader<File>>,
+) -> anyhow::Result<Option<StringRecord>> {
+    let Some(record) = run.next().transpose()? else {
+        return Ok(None);
+    };
+    let mut cells = record.iter();
+    let line = cells.next_back().unwrap_or_default().parse().unwrap_or(0);
+    let mut record: StringRecord = cells.collect();
+    if line > 0 {
+        let mut position = Position::new();
+        position.set_line(line);
+        record.set_position(Some(position));
+    }
+    Ok(Some(record))
+}
+
+/// K-way merge of at most `MERGE_FAN_IN` sorted runs, the smallest head is found with
+/// a linear scan; ties go to the earlier run, which keeps the sort stable.
+struct Merge {
+    sorter: RowSorter,
+    runs: Vec<StringRecordsIntoIter<BufReader<File>>>,
+    heads: Vec<Option<StringRecord>>,
+    // removes the run files once the merge is dropped
+    _paths: Vec<TempPath>,
+}
+
+impl Iterator for Merge {
+    type Item = anyhow::Result<StringRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut smallest: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            let Some(head) = head else { continue };
+            match smallest.and_then(|s| self.heads[s].as_ref()) {
+                Some(current) if self.sorter.compare(head, current).is_ge() => {}
+                _ => smallest = Some(i),
+            }
+        }
+        let i = smallest?;
+        let next = match next_spilled(&mut self.runs[i]) {
+            Ok(next) => next,
+            Err(e) => return Some(Err(e)),
+        };
+        std::mem::replace(&mut self.heads[i], next).map(Ok)
+    }
+}
+
+/// Drops rows whose key columns were seen before, keeping the first occurrence.
+///
+/// Rows are sorted by their key with the external sort, the first row of every key is
+/// kept and the rest are sorted back into input order by line number. Memory stays
+/// within the sort buffer, at the cost of reading all rows before the first comes out.
+pub(super) struct RowDedup {
+    columns: Option<Vec<usize>>,
+    buffer: usize,
+}
+
+impl RowDedup {
+    pub(super) fn new(
+        columns: &[String],
+        headers: Option<&StringRecord>,
+        buffer: usize,
+    ) -> anyhow::Result<Self> {
+        let columns = match columns {
+            [] => None,
+            columns => Some(
+                columns
+                    .iter()
+                    .map(|c| {
+                        column_index(headers, c).map_err(|e| anyhow::anyhow!("{} in --dedup", e))
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+        };
+        Ok(Self {
+            columns,
+            buffer: buffer.max(1),
+        })
+    }
+
+    pub(super) fn dedup<'a>(self, rows: Rows<'a>) -> anyhow::Result<Rows<'a>> {
+        let by_key = RowSorter {
+            order: Order::Exact(self.columns),
+            buffer: self.buffer,
+        };
+        let mut last: Option<StringRecord> = None;
+        let sorted = by_key.clone().sort(rows)?;
+        // the sort is stable, so the first row of a key is the first in the input
+        let first = sorted.filter(move |record| {
+            let Ok(record) = record else { return true };
+            let new = last
+                .as_ref()
+                .is_none_or(|last| by_key.compare(last, record).is_ne());
+            if new {
+                last = Some(record.clone());
+            }
+            new
+        });
+        RowSorter {
+            order: Order::Line,
+            buffer: self.buffer,
+        }
+        .sort(Box::new(first))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(data: &[(&str, &str)]) -> Rows<'static> {
+        let rows: Vec<_> = data
+            .iter()
+            .map(|(a, b)| Ok(StringRecord::from(vec![*a, *b])))
+            .collect();
+        Box::new(rows.into_iter())
+    }
+
+    fn sorted(keys: &[SortKey], buffer: usize, data: &[(&str, &str)]) -> Vec<String> {
+        let headers = StringRecord::from(vec!["name", "kit"]);
+        let sorter = RowSorter::new(keys, Some(&headers), buffer).unwrap();
+        sorter
+            .sort(rows(data))
+            .unwrap()
+            .map(|r| r.unwrap().iter().collect::<Vec<_>>().join(":"))
+            .collect()
+    }
+
+    const DATA: &[(&str, &str)] = &[
+        ("Perin", "37"),
+        ("Buffon", "77"),
+        ("Szczesny", "1"),
+        ("Pinsoglio", "31"),
+        ("Danilo", "13"),
+        ("Alex", "13"),
+    ];
+
+    fn key(column: &str, descending: bool) -> SortKey {
+        SortKey {
+            column: column.to_string(),
+            descending,
+        }
+    }
+
+    #[test]
+    fn test_sort_numeric_aware_and_stable() {
+        let expected = vec![
+            "Szczesny:1",
+            "Danilo:13",
+            "Alex:13",
+            "Pinsoglio:31",
+            "Perin:37",
+            "Buffon:77",
+        ];
+        assert_eq!(sorted(&[key("kit", false)], 100, DATA), expected);
+    }
+
+    #[test]
+    fn test_external_sort_matches_in_memory() {
+        let keys = [key("kit", true), key("name", false)];
+        assert_eq!(sorted(&keys, 2, DATA), sorted(&keys, 100, DATA));
+        assert_eq!(sorted(&keys, 2, DATA)[3], "Alex:13");
+    }
+
+    #[test]
+    fn test_external_sort_merges_in_passes() {
+        // one row per run, so runs are merged in groups before the final merge
+        let names: Vec<String> = (0..MERGE_FAN_IN * 3 + 5)
+            .map(|i| format!("p{}", i))
+            .collect();
+        let kits = ["3", "1", "2"];
+        let data: Vec<(&str, &str)> = names
+            .iter()
+            .zip(kits.iter().cycle())
+            .map(|(name, kit)| (name.as_str(), *kit))
+            .collect();
+        let keys = [key("kit", false)];
+        assert_eq!(sorted(&keys, 1, &data), sorted(&keys, data.len(), &data));
+    }
+
+    fn deduped(columns: &[&str], buffer: usize, data: &[(&str, &str)]) -> Vec<String> {
+        let headers = StringRecord::from(vec!["name", "kit"]);
+        let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+        // number the rows like a reader would, input order is restored by line
+        let rows = rows(data).enumerate().map(|(i, record)| {
+            let mut record = record?;
+            let mut position = Position::new();
+            position.set_line(i as u64 + 2);
+            record.set_position(Some(position));
+            Ok(record)
+        });
+        RowDedup::new(&columns, Some(&headers), buffer)
+            .unwrap()
+            .dedup(Box::new(rows))
+            .unwrap()
+            .map(|r| r.unwrap().iter().collect::<Vec<_>>().join(":"))
+            .collect()
+    }
+
+    #[test]
+    fn test_dedup_by_column() {
+        let kept = deduped(&["kit"], 100, DATA);
+        assert_eq!(
+            kept,
+            vec![
+                "Perin:37",
+                "Buffon:77",
+                "Szczesny:1",
+                "Pinsoglio:31",
+                "Danilo:13"
+            ]
+        );
+        assert_eq!(deduped(&["kit"], 2, DATA), kept);
+    }
+
+    #[test]
+    fn test_dedup_exact_cells() {
+        // 13 and 13.0 sort as equal numbers but are different cells
+        let data = &[("a", "13"), ("b", "13.0"), ("a", "13"), ("c", "13.0")];
+        assert_eq!(deduped(&[], 1, data), vec!["a:13", "b:13.0", "c:13.0"]);
+        assert_eq!(deduped(&["kit"], 1, data), vec!["a:13", "b:13.0"]);
+    }
+}
@@ -0,0 +1,242 @@
+use super::{column_index, csv_reader, read_headers, read_rows, table_name, CsvConfig};
+use crate::{get_writer, OutputWriter, SplitBy};
+use csv::{Writer, WriterBuilder};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::BufWriter,
+    path::Path,
+};
+
+type PartWriter = Writer<OutputWriter>;
+
+/// Part files open at once with `--by`. Past that the least recently written one is
+/// closed, and reopened for appending when its value comes up again.
+const MAX_OPEN_PARTS: usize = 64;
+
+/// Break `input` into CSV files in `dir`, every one starting with the header row.
+///
+/// Parts are `players_1.csv`, `players_2.csv`, ... with `--rows`, or named after the
+/// column value with `--by`, like `players_Italy.csv`. Rows are streamed, with `--by`
+/// at most `MAX_OPEN_PARTS` files are open at a time. Returns the paths written.
+fn csv_split(
+    input: &str,
+    dir: &str,
+    by: &SplitBy,
+    config: &CsvConfig,
+) -> anyhow::Result<Vec<String>> {
+    let mut rdr = csv_reader(input, config)?;
+    let headers = read_headers(&mut rdr)?;
+    let stem = table_name(input).unwrap_or_else(|| "stdin".to_string());
+    let by_index = match by {
+        SplitBy::Rows(0) => return Err(anyhow::anyhow!("--rows has to be at least 1")),
+        SplitBy::Rows(_) => None,
+        SplitBy::Column(column) => Some(column_index(headers.as_ref(), column)?),
+    };
+    std::fs::create_dir_all(dir)?;
+    // a new part starts with the header, a reopened one is appended to
+    let part = |name: &str, reopen: bool| -> anyhow::Result<(String, PartWriter)> {
+        let path = Path::new(dir).join(format!("{}_{}.csv", stem, name));
+        let writer = match reopen {
+            true => {
+                OutputWriter::File(BufWriter::new(OpenOptions::new().append(true).open(&path)?))
+            }
+            false => get_writer(&path.to_string_lossy())?,
+        };
+        let mut wtr = WriterBuilder::new()
+            .delimiter(config.output_delimiter() as u8)
+            .from_writer(writer);
+        if let (Some(headers), false) = (&headers, reopen) {
+            wtr.write_record(headers)?;
+        }
+        Ok((path.to_string_lossy().into_owned(), wtr))
+    };
+
+    let mut paths = Vec::new();
+    // part names by column value, and the names taken
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut taken = HashSet::new();
+    // open parts by column value, with the row they were last written
+    let mut parts: HashMap<String, (PartWriter, usize)> = HashMap::new();
+    let mut current: Option<PartWriter> = None;
+    for (i, record) in read_rows(rdr, headers.as_ref(), config)?.enumerate() {
+        let record = record?;
+        let wtr = match (by, by_index) {
+            (_, Some(index)) => {
+                let value = record.get(index).unwrap_or_default();
+                if !parts.contains_key(value) {
+                    if parts.len() >= MAX_OPEN_PARTS {
+                        let oldest = parts
+                            .iter()
+                            .min_by_key(|(_, (_, used))| *used)
+                            .map(|(value, _)| value.clone())
+                            .expect("parts are open");
+                        let (wtr, _) = parts.remove(&oldest).expect("oldest part is open");
+                        finish(wtr)?;
+                    }
+                    let (name, reopen) = match names.get(value) {
+                        Some(name) => (name.clone(), true),
+                        None => {
+                            let name = unique_name(file_name(value), &mut taken);
+                            names.insert(value.to_string(), name.clone());
+                            (name, false)
+                        }
+                    };
+                    let (path, wtr) = part(&name, reopen)?;
+                    if !reopen {
+                        paths.push(path);
+                    }
+                    parts.insert(value.to_string(), (wtr, i));
+                }
+                let (wtr, used) = parts.get_mut(value).expect("part was just opened");
+                *used = i;
+                wtr
+            }
+            (SplitBy::Rows(rows), _) => {
+                if i % rows == 0 {
+                    if let Some(wtr) = current.take() {
+                        finish(wtr)?;
+                    }
+                    let (path, wtr) = part(&(i / rows + 1).to_string(), false)?;
+                    paths.push(path);
+                    current = Some(wtr);
+                }
+                current.as_mut().expect("part was just opened")
+            }
+            (SplitBy::Column(_), None) => unreachable!("the column index is resolved"),
+        };
+        wtr.write_record(&record)?;
+    }
+    for wtr in parts.into_values().map(|(wtr, _)| wtr).chain(current) {
+        finish(wtr)?;
+    }
+    Ok(paths)
+}
+
+fn finish(wtr: PartWriter) -> anyhow::Result<()> {
+    wtr.into_inner().map_err(|e| e.into_error())?.finish()
+}
+
+/// `name`, or `name_2`, `name_3`, ... when another value already got it, so values
+/// like `a b` and `a/b` that read the same in a file name still get their own parts.
+fn unique_name(name: String, taken: &mut HashSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut n = 1;
+    while !taken.insert(unique.clone()) {
+        n += 1;
+        unique = format!("{}_{}", name, n);
+    }
+    unique
+}
+
+/// A column value usable in a file name, `Costa Rica` becomes `Costa_Rica`.
+fn file_name(value: &str) -> String {
+    if value.is_empty() {
+        return "empty".to_string();
+    }
+    value
+        .chars()
+        .map(|c| match c.is_alphanumeric() || matches!(c, '-' | '.') {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+pub fn process_csv_split(
+    input: &str,
+    dir: &str,
+    by: &SplitBy,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let paths = csv_split(input, dir, by, config)?;
+    eprintln!("Wrote {} files to {}", paths.len(), dir);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(data: &str, by: SplitBy) -> Vec<(String, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("players.csv");
+        std::fs::write(&input, data).unwrap();
+        let out = dir.path().join("parts");
+        let paths = csv_split(
+            input.to_str().unwrap(),
+            out.to_str().unwrap(),
+            &by,
+            &CsvConfig::default(),
+        )
+        .unwrap();
+        paths
+            .iter()
+            .map(|path| {
+                let name = Path::new(path).file_name().unwrap().to_string_lossy();
+                (name.into_owned(), std::fs::read_to_string(path).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_rows() {
+        let parts = split("name,kit\na,1\nb,2\nc,3\n", SplitBy::Rows(2));
+        assert_eq!(
+            parts,
+            vec![
+                ("players_1.csv".into(), "name,kit\na,1\nb,2\n".into()),
+                ("players_2.csv".into(), "name,kit\nc,3\n".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_by_column() {
+        let data = "name,country\na,Italy\nb,Costa Rica\nc,Italy\nd,\n";
+        let parts = split(data, SplitBy::Column("country".into()));
+        let names: Vec<_> = parts.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "players_Italy.csv",
+                "players_Costa_Rica.csv",
+                "players_empty.csv"
+            ]
+        );
+        assert_eq!(parts[0].1, "name,country\na,Italy\nc,Italy\n");
+    }
+
+    #[test]
+    fn test_split_by_column_colliding_names() {
+        let data = "name,city\na,a b\nb,a/b\nc,\nd,empty\ne,a b\n";
+        let parts = split(data, SplitBy::Column("city".into()));
+        assert_eq!(
+            parts,
+            vec![
+                ("players_a_b.csv".into(), "name,city\na,a b\ne,a b\n".into()),
+                ("players_a_b_2.csv".into(), "name,city\nb,a/b\n".into()),
+                ("players_empty.csv".into(), "name,city\nc,\n".into()),
+                ("players_empty_2.csv".into(), "name,city\nd,empty\n".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_by_column_beyond_open_parts() {
+        // every value comes up twice, after all the others, so each part gets reopened
+        let values = MAX_OPEN_PARTS * 2;
+        let mut data = "id,value\n".to_string();
+        for round in 0..2 {
+            for v in 0..values {
+                data.push_str(&format!("{},{}\n", round, v));
+            }
+        }
+        let parts = split(&data, SplitBy::Column("value".into()));
+        assert_eq!(parts.len(), values);
+        for (v, (name, contents)) in parts.iter().enumerate() {
+            assert_eq!(name, &format!("players_{}.csv", v));
+            assert_eq!(contents, &format!("id,value\n0,{}\n1,{}\n", v, v));
+        }
+    }
+}
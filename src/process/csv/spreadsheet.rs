@@ -0,0 +1,189 @@
+use super::{record_types, CsvConfig, CsvRecord};
+use crate::CsvType;
+use calamine::{open_workbook_auto, Data, Reader as _};
+use csv::{Reader, ReaderBuilder, WriterBuilder};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+use serde_json::Value;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+const EXTENSIONS: [&str; 5] = ["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+/// Whether `input` is an Excel or OpenDocument workbook, by its extension.
+pub(super) fn is_spreadsheet(input: &str) -> bool {
+    Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Read a sheet of a workbook as if it were a CSV file, so spreadsheets go through the
+/// same row pipeline as CSV input.
+///
+/// `config.sheet` picks the sheet by name or 1-based position, the first one by default.
+/// The first row is the header unless `--no-header` is given.
+pub(super) fn sheet_reader(
+    input: &str,
+    config: &CsvConfig,
+) -> anyhow::Result<Reader<Box<dyn Read>>> {
+    let mut workbook = open_workbook_auto(input)?;
+    let names = workbook.sheet_names();
+    let name = match config.sheet.as_deref() {
+        None => {
+            let first = names
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("{} has no sheets", input))?;
+            if names.len() > 1 {
+                eprintln!(
+                    "Reading sheet `{}` of {}, pick another with --sheet",
+                    first, input
+                );
+            }
+            first.clone()
+        }
+        Some(sheet) if names.iter().any(|name| name == sheet) => sheet.to_string(),
+        Some(sheet) => sheet
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| names.get(n.checked_sub(1)?))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No sheet `{}` in {}, it has {}",
+                    sheet,
+                    input,
+                    names.join(", ")
+                )
+            })?,
+    };
+    let range = workbook.worksheet_range(&name)?;
+
+    let mut wtr = WriterBuilder::new().flexible(true).from_writer(Vec::new());
+    for row in range.rows() {
+        wtr.write_record(row.iter().map(cell))?;
+    }
+    let buf = wtr.into_inner().map_err(|e| e.into_error())?;
+    Ok(ReaderBuilder::new()
+        .has_headers(config.headers.unwrap_or(true))
+        .flexible(true)
+        .from_reader(Box::new(Cursor::new(buf))))
+}
+
+/// The text of a cell, dates as `YYYY-MM-DD` so they are typed as dates again.
+fn cell(data: &Data) -> String {
+    match data {
+        Data::DateTime(dt) if dt.is_datetime() => {
+            let (y, m, d, hh, mm, ss, _) = dt.to_ymd_hms_milli();
+            match (hh, mm, ss) {
+                (0, 0, 0) => format!("{:04}-{:02}-{:02}", y, m, d),
+                _ => format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", y, m, d, hh, mm, ss),
+            }
+        }
+        data => data.to_string(),
+    }
+}
+
+/// Write an XLSX workbook with one sheet named after the table, a bold header row and
+/// columns sized to fit. Numbers, booleans and dates are stored as typed cells.
+pub(super) fn write_xlsx<W: Write>(
+    csv_record: &CsvRecord,
+    writer: &mut W,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let types = record_types(csv_record, &config.types)?;
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    if let Some(name) = config.table.as_deref().map(sheet_name) {
+        sheet.set_name(name)?;
+    }
+    let bold = Format::new().set_bold();
+    let date = Format::new().set_num_format("yyyy-mm-dd");
+
+    let mut row = 0;
+    if let Some(headers) = &csv_record.headers {
+        for (col, header) in headers.iter().enumerate() {
+            sheet.write_string_with_format(row, col as u16, header, &bold)?;
+        }
+        row += 1;
+    }
+    for record in &csv_record.records {
+        for (col, (cell, ty)) in record.iter().zip(&types).enumerate() {
+            let col = col as u16;
+            match ty.convert(cell)? {
+                Value::Null => {}
+                Value::Bool(b) => {
+                    sheet.write_boolean(row, col, b)?;
+                }
+                Value::Number(n) => {
+                    sheet.write_number(row, col, n.as_f64().unwrap_or_default())?;
+                }
+                Value::String(s) if *ty == CsvType::Date => {
+                    let dt = ExcelDateTime::parse_from_str(&s)?;
+                    sheet.write_datetime_with_format(row, col, dt, &date)?;
+                }
+                value => {
+                    let s = value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string);
+                    sheet.write_string(row, col, s)?;
+                }
+            }
+        }
+        row += 1;
+    }
+    sheet.autofit();
+
+    writer.write_all(&workbook.save_to_buffer()?)?;
+    Ok(())
+}
+
+/// A valid sheet name: at most 31 characters and none of `[]:*?/\`.
+fn sheet_name(table: &str) -> String {
+    table
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .take(31)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xlsx_round_trip() {
+        let csv_record: CsvRecord = csv::Reader::from_reader(
+            "name,kit,keeper,born\nPerin,37,true,1992-11-10\nBuffon,,false,1978-01-28\n".as_bytes(),
+        )
+        .try_into()
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("players.xlsx");
+        let config = CsvConfig {
+            table: Some("players".to_string()),
+            ..Default::default()
+        };
+        let mut file = std::fs::File::create(&path).unwrap();
+        write_xlsx(&csv_record, &mut file, &config).unwrap();
+        drop(file);
+
+        let path = path.to_str().unwrap();
+        assert!(is_spreadsheet(path));
+        let config = CsvConfig {
+            sheet: Some("players".to_string()),
+            ..Default::default()
+        };
+        let back: CsvRecord = sheet_reader(path, &config).unwrap().try_into().unwrap();
+        assert_eq!(back.headers, csv_record.headers);
+        assert_eq!(back.records, csv_record.records);
+
+        let config = CsvConfig {
+            sheet: Some("2".to_string()),
+            ..Default::default()
+        };
+        assert!(sheet_reader(path, &config).is_err());
+    }
+}
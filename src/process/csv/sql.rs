@@ -0,0 +1,290 @@
+use super::{record_types, CsvConfig, CsvRecord};
+use crate::{CsvType, SqlDialect};
+use rusqlite::{types::Value as SqlValue, Connection};
+use serde_json::Value;
+use std::io::Write;
+
+/// Table name when neither `--table` nor an input file name gives one.
+const DEFAULT_TABLE: &str = "data";
+
+/// Write a script that replaces the table and fills it with batched `INSERT`s, in one
+/// transaction. Column types are inferred as for Parquet and empty typed cells are NULL.
+pub(super) fn write_sql<W: Write>(
+    csv_record: &CsvRecord,
+    mut writer: W,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let dialect = config.sql_dialect;
+    let name = config.table.as_deref().unwrap_or(DEFAULT_TABLE);
+    let table = SqlTable::new(csv_record, name, config)?;
+    let name = quote_identifier(dialect, &table.name);
+    let columns: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| quote_identifier(dialect, c))
+        .collect();
+
+    writeln!(writer, "BEGIN;")?;
+    writeln!(writer, "DROP TABLE IF EXISTS {};", name)?;
+    writeln!(writer, "CREATE TABLE {} (", name)?;
+    let definitions: Vec<String> = columns
+        .iter()
+        .zip(&table.types)
+        .map(|(column, ty)| format!("  {} {}", column, column_type(dialect, *ty)))
+        .collect();
+    writeln!(writer, "{}", definitions.join(",\n"))?;
+    writeln!(writer, ");")?;
+
+    for rows in csv_record.records.chunks(config.batch_size.max(1)) {
+        writeln!(
+            writer,
+            "INSERT INTO {} ({}) VALUES",
+            name,
+            columns.join(", ")
+        )?;
+        let rows = rows
+            .iter()
+            .map(|row| {
+                let values = table
+                    .values(row)?
+                    .iter()
+                    .map(|value| literal(dialect, value))
+                    .collect::<Vec<_>>();
+                Ok(format!("  ({})", values.join(", ")))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        writeln!(writer, "{};", rows.join(",\n"))?;
+    }
+    writeln!(writer, "COMMIT;")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Replace the table in a SQLite database file, creating the file if needed.
+pub(super) fn write_sqlite(
+    csv_record: &CsvRecord,
+    path: &str,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    if path == "-" {
+        return Err(anyhow::anyhow!(
+            "SQLite output is written into a database file, pass --output"
+        ));
+    }
+    let name = config.table.as_deref().unwrap_or(DEFAULT_TABLE);
+    let mut conn = Connection::open(path)?;
+    let tx = conn.transaction()?;
+    load_table(&tx, csv_record, name, config)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Create the table `name`, dropping any old one, and insert every row into it.
+pub(super) fn load_table(
+    conn: &Connection,
+    csv_record: &CsvRecord,
+    name: &str,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let table = SqlTable::new(csv_record, name, config)?;
+    let name = quote_identifier(SqlDialect::Sqlite, &table.name);
+    let definitions: Vec<String> = table
+        .columns
+        .iter()
+        .zip(&table.types)
+        .map(|(column, ty)| {
+            let column = quote_identifier(SqlDialect::Sqlite, column);
+            format!("{} {}", column, column_type(SqlDialect::Sqlite, *ty))
+        })
+        .collect();
+    let placeholders = vec!["?"; table.columns.len()].join(", ");
+
+    conn.execute(&format!("DROP TABLE IF EXISTS {}", name), [])?;
+    conn.execute(
+        &format!("CREATE TABLE {} ({})", name, definitions.join(", ")),
+        [],
+    )?;
+    let mut insert = conn.prepare(&format!("INSERT INTO {} VALUES ({})", name, placeholders))?;
+    for row in &csv_record.records {
+        let values = table.values(row)?.into_iter().map(sqlite_value);
+        insert.execute(rusqlite::params_from_iter(values))?;
+    }
+    Ok(())
+}
+
+/// The name, columns and column types of the table a record is loaded into.
+struct SqlTable {
+    name: String,
+    columns: Vec<String>,
+    types: Vec<CsvType>,
+}
+
+impl SqlTable {
+    fn new(csv_record: &CsvRecord, name: &str, config: &CsvConfig) -> anyhow::Result<Self> {
+        let types = record_types(csv_record, &config.types)?;
+        let columns = (0..types.len())
+            .map(|i| {
+                csv_record
+                    .headers
+                    .as_ref()
+                    .and_then(|h| h.get(i))
+                    .map_or_else(|| format!("column_{}", i + 1), str::to_string)
+            })
+            .collect();
+        Ok(Self {
+            name: name.to_string(),
+            columns,
+            types,
+        })
+    }
+
+    /// Typed cells of a row, padded with nulls to the table width.
+    fn values(&self, row: &csv::StringRecord) -> anyhow::Result<Vec<Value>> {
+        self.types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| match row.get(i) {
+                Some(cell) => ty.convert(cell).map_err(|e| {
+                    let line = row.position().map_or(0, |p| p.line());
+                    anyhow::anyhow!("{} in column `{}` on line {}", e, self.columns[i], line)
+                }),
+                None => Ok(Value::Null),
+            })
+            .collect()
+    }
+}
+
+fn column_type(dialect: SqlDialect, ty: CsvType) -> &'static str {
+    match (dialect, ty) {
+        (_, CsvType::String) => "TEXT",
+        (SqlDialect::Sqlite, CsvType::Integer | CsvType::Boolean) => "INTEGER",
+        (SqlDialect::Sqlite, CsvType::Float) => "REAL",
+        // SQLite has no date type, ISO dates in TEXT sort and compare correctly
+        (SqlDialect::Sqlite, CsvType::Date) => "TEXT",
+        (_, CsvType::Integer) => "BIGINT",
+        (SqlDialect::Postgres, CsvType::Float) => "DOUBLE PRECISION",
+        (SqlDialect::Mysql, CsvType::Float) => "DOUBLE",
+        (_, CsvType::Boolean) => "BOOLEAN",
+        (_, CsvType::Date) => "DATE",
+    }
+}
+
+pub(super) fn quote_identifier(dialect: SqlDialect, name: &str) -> String {
+    match dialect {
+        SqlDialect::Mysql => format!("`{}`", name.replace('`', "``")),
+        SqlDialect::Sqlite | SqlDialect::Postgres => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+fn literal(dialect: SqlDialect, value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) if dialect == SqlDialect::Sqlite => (*b as u8).to_string(),
+        Value::Bool(b) => b.to_string().to_uppercase(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => {
+            let mut quoted = s.replace('\'', "''");
+            // backslash is an escape character in MySQL string literals by default
+            if dialect == SqlDialect::Mysql {
+                quoted = quoted.replace('\\', "\\\\");
+            }
+            format!("'{}'", quoted)
+        }
+        value => format!("'{}'", value.to_string().replace('\'', "''")),
+    }
+}
+
+fn sqlite_value(value: Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s),
+        value => SqlValue::Text(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::StringRecord;
+
+    fn players() -> CsvRecord {
+        CsvRecord {
+            headers: Some(StringRecord::from(vec!["Name", "Kit Number", "Active"])),
+            records: vec![
+                StringRecord::from(vec!["O'Brien", "37", "true"]),
+                StringRecord::from(vec!["Buffon", "", "false"]),
+                StringRecord::from(vec!["a\\b", "1", ""]),
+            ],
+        }
+    }
+
+    fn sql(dialect: SqlDialect, batch_size: usize) -> String {
+        let config = CsvConfig {
+            sql_dialect: dialect,
+            table: Some("players".to_string()),
+            batch_size,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        write_sql(&players(), &mut buf, &config).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_sql_postgres_script() {
+        assert_eq!(
+            sql(SqlDialect::Postgres, 2),
+            "BEGIN;\n\
+             DROP TABLE IF EXISTS \"players\";\n\
+             CREATE TABLE \"players\" (\n  \"Name\" TEXT,\n  \"Kit Number\" BIGINT,\n  \"Active\" BOOLEAN\n);\n\
+             INSERT INTO \"players\" (\"Name\", \"Kit Number\", \"Active\") VALUES\n  ('O''Brien', 37, TRUE),\n  ('Buffon', NULL, FALSE);\n\
+             INSERT INTO \"players\" (\"Name\", \"Kit Number\", \"Active\") VALUES\n  ('a\\b', 1, NULL);\n\
+             COMMIT;\n"
+        );
+    }
+
+    #[test]
+    fn test_sql_mysql_quoting() {
+        let output = sql(SqlDialect::Mysql, 500);
+        assert!(output.contains("CREATE TABLE `players` (\n  `Name` TEXT,\n  `Kit Number` BIGINT,"));
+        assert!(output.contains("  ('a\\\\b', 1, NULL);\n"));
+        let output = sql(SqlDialect::Sqlite, 500);
+        assert!(output.contains("\"Active\" INTEGER\n"));
+        assert!(output.contains("  ('O''Brien', 37, 1),\n"));
+    }
+
+    #[test]
+    fn test_sqlite_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.db");
+        let path = path.to_str().unwrap();
+        let config = CsvConfig {
+            table: Some("players".to_string()),
+            ..Default::default()
+        };
+        // a second load replaces the table instead of appending to it
+        write_sqlite(&players(), path, &config).unwrap();
+        write_sqlite(&players(), path, &config).unwrap();
+
+        let conn = Connection::open(path).unwrap();
+        let (count, kits): (i64, i64) = conn
+            .query_row(
+                "SELECT count(*), sum(\"Kit Number\") FROM players",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((count, kits), (3, 38));
+        let name: String = conn
+            .query_row("SELECT Name FROM players WHERE Active = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(name, "O'Brien");
+    }
+}
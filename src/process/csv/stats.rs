@@ -0,0 +1,240 @@
+use super::{csv_output, csv_reader, read_headers, write_contents, CsvConfig, CsvRecord};
+use crate::{CsvType, OutputFormat};
+use csv::StringRecord;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize)]
+pub struct CsvStats {
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnStats {
+    pub column: String,
+    #[serde(rename = "type")]
+    pub ty: CsvType,
+    pub count: usize,
+    pub distinct: usize,
+    pub nulls: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub median: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top: Vec<TopValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopValue {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Default)]
+struct ColumnAccumulator {
+    ty: Option<CsvType>,
+    nulls: usize,
+    counts: HashMap<String, usize>,
+    numbers: Vec<f64>,
+}
+
+impl ColumnAccumulator {
+    fn add(&mut self, cell: &str) {
+        if cell.is_empty() {
+            self.nulls += 1;
+            return;
+        }
+        let ty = CsvType::of(cell);
+        self.ty = Some(self.ty.map_or(ty, |t| t.merge(ty)));
+        if let Ok(number) = cell.parse::<f64>() {
+            self.numbers.push(number);
+        }
+        *self.counts.entry(cell.to_string()).or_default() += 1;
+    }
+
+    fn finish(mut self, column: String, top: usize) -> ColumnStats {
+        let ty = self.ty.unwrap_or(CsvType::String);
+        let count = self.counts.values().sum();
+        let distinct = self.counts.len();
+        let mut stats = ColumnStats {
+            column,
+            ty,
+            count,
+            distinct,
+            nulls: self.nulls,
+            min: None,
+            max: None,
+            mean: None,
+            median: None,
+            top: vec![],
+        };
+
+        if matches!(ty, CsvType::Integer | CsvType::Float) && !self.numbers.is_empty() {
+            self.numbers.sort_by(f64::total_cmp);
+            let n = self.numbers.len();
+            let number = |x: f64| match ty {
+                CsvType::Integer => Value::from(x as i64),
+                _ => Value::from(x),
+            };
+            stats.min = Some(number(self.numbers[0]));
+            stats.max = Some(number(self.numbers[n - 1]));
+            stats.mean = Some(self.numbers.iter().sum::<f64>() / n as f64);
+            stats.median = Some(match n % 2 {
+                0 => (self.numbers[n / 2 - 1] + self.numbers[n / 2]) / 2.0,
+                _ => self.numbers[n / 2],
+            });
+        } else {
+            let mut counts: Vec<_> = self.counts.into_iter().collect();
+            counts.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+            stats.top = counts
+                .into_iter()
+                .take(top)
+                .map(|(value, count)| TopValue { value, count })
+                .collect();
+        }
+        stats
+    }
+}
+
+fn csv_stats(input: &str, config: &CsvConfig, top: usize) -> anyhow::Result<CsvStats> {
+    let mut rdr = csv_reader(input, config)?;
+    let headers = read_headers(&mut rdr)?;
+    let mut columns: Vec<ColumnAccumulator> = Vec::new();
+
+    for record in rdr.into_records() {
+        let record = record?;
+        if columns.len() < record.len() {
+            columns.resize_with(record.len(), Default::default);
+        }
+        for (column, cell) in columns.iter_mut().zip(record.iter()) {
+            column.add(cell);
+        }
+    }
+
+    let width = headers.as_ref().map_or(columns.len(), |h| h.len());
+    columns.resize_with(width, Default::default);
+    let columns = columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let name = headers
+                .as_ref()
+                .and_then(|h| h.get(i))
+                .map_or_else(|| i.to_string(), str::to_string);
+            column.finish(name, top)
+        })
+        .collect();
+    Ok(CsvStats { columns })
+}
+
+impl From<CsvStats> for CsvRecord {
+    /// One row per input column, for the raw view.
+    fn from(stats: CsvStats) -> Self {
+        let cell = |value: Option<String>| value.unwrap_or_default();
+        let headers = [
+            "column", "type", "count", "distinct", "nulls", "min", "max", "mean", "median", "top",
+        ];
+        let records = stats
+            .columns
+            .into_iter()
+            .map(|c| {
+                let top = c
+                    .top
+                    .iter()
+                    .map(|t| format!("{} ({})", t.value, t.count))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                StringRecord::from(vec![
+                    c.column,
+                    c.ty.as_str().to_string(),
+                    c.count.to_string(),
+                    c.distinct.to_string(),
+                    c.nulls.to_string(),
+                    cell(c.min.map(|v| v.to_string())),
+                    cell(c.max.map(|v| v.to_string())),
+                    cell(c.mean.map(|v| format!("{:.2}", v))),
+                    cell(c.median.map(|v| v.to_string())),
+                    top,
+                ])
+            })
+            .collect();
+        Self {
+            headers: Some(StringRecord::from(headers.to_vec())),
+            records,
+        }
+    }
+}
+
+pub fn process_csv_stats(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    top: usize,
+    config: &CsvConfig,
+) -> anyhow::Result<()> {
+    let stats = csv_stats(input, config, top)?;
+    let contents = match format {
+        OutputFormat::Raw
+        | OutputFormat::Csv
+        | OutputFormat::Markdown
+        | OutputFormat::Html
+        | OutputFormat::Xml
+        | OutputFormat::Parquet
+        | OutputFormat::Arrow
+        | OutputFormat::Sql
+        | OutputFormat::Sqlite
+        | OutputFormat::Xlsx => return csv_output(stats.into(), output, format, config),
+        OutputFormat::Json => serde_json::to_string_pretty(&stats)?,
+        OutputFormat::Jsonl => stats
+            .columns
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n"),
+        OutputFormat::Yaml => serde_yaml::to_string(&stats)?,
+        OutputFormat::Toml => toml::to_string(&stats)?,
+    };
+    write_contents(output, &contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_stats() {
+        let stats = csv_stats("assets/juventus.csv", &CsvConfig::default(), 2).unwrap();
+        let kit = &stats.columns[4];
+        assert_eq!(kit.ty, CsvType::Integer);
+        assert_eq!(kit.count, 27);
+        assert_eq!(kit.nulls, 0);
+        assert_eq!(kit.min, Some(Value::from(1)));
+        assert_eq!(kit.max, Some(Value::from(77)));
+        assert!(kit.top.is_empty());
+
+        let nationality = &stats.columns[3];
+        assert!(nationality.mean.is_none());
+        assert_eq!(nationality.top.len(), 2);
+        assert_eq!(nationality.top[0].value, "Italy");
+    }
+
+    #[test]
+    fn test_column_accumulator_median_and_nulls() {
+        let mut column = ColumnAccumulator::default();
+        for cell in ["4", "", "1.5", "2", "10"] {
+            column.add(cell);
+        }
+        let stats = column.finish("x".to_string(), 5);
+        assert_eq!(stats.ty, CsvType::Float);
+        assert_eq!(stats.nulls, 1);
+        assert_eq!(stats.median, Some(3.0));
+        assert_eq!(stats.mean, Some(4.375));
+    }
+}
@@ -0,0 +1,136 @@
+use super::CsvRecord;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+impl CsvRecord {
+    /// Render an aligned, boxed table for reading in a terminal.
+    ///
+    /// Widths are measured in terminal columns, so CJK text and emoji line up. Cells wider
+    /// than `max_width` are cut with `…`, 0 disables the limit. Numeric columns are right
+    /// aligned.
+    pub(super) fn to_table(&self, max_width: usize) -> String {
+        let rows: Vec<Vec<String>> = self
+            .headers
+            .iter()
+            .chain(&self.records)
+            .map(|record| record.iter().map(|c| fit(c, max_width)).collect())
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+
+        let mut widths = vec![0; columns];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        let body = &rows[self.headers.is_some() as usize..];
+        let numeric: Vec<bool> = (0..columns)
+            .map(|i| {
+                let mut cells = body
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .filter(|c| !c.is_empty())
+                    .peekable();
+                cells.peek().is_some() && cells.all(|c| c.parse::<f64>().is_ok())
+            })
+            .collect();
+
+        let border = |left: &str, middle: &str, right: &str| {
+            let lines: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+            format!("{}{}{}", left, lines.join(middle), right)
+        };
+        let line = |row: &[String], align: bool| {
+            let cells: Vec<String> = (0..columns)
+                .map(|i| {
+                    let cell = row.get(i).map_or("", String::as_str);
+                    let pad = " ".repeat(widths[i] - cell.width());
+                    match align && numeric[i] {
+                        true => format!(" {}{} ", pad, cell),
+                        false => format!(" {}{} ", cell, pad),
+                    }
+                })
+                .collect();
+            format!("│{}│", cells.join("│"))
+        };
+
+        let mut lines = vec![border("┌", "┬", "┐")];
+        if self.headers.is_some() {
+            lines.push(line(&rows[0], false));
+            lines.push(border("├", "┼", "┤"));
+        }
+        lines.extend(body.iter().map(|row| line(row, true)));
+        lines.push(border("└", "┴", "┘"));
+        lines.join("\n")
+    }
+}
+
+/// Flatten line breaks and tabs to spaces and cut the cell down to `max_width` columns.
+fn fit(cell: &str, max_width: usize) -> String {
+    let cell: String = cell
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if max_width == 0 || cell.width() <= max_width {
+        return cell;
+    }
+    let mut width = 0;
+    let mut fitted = String::new();
+    for c in cell.chars() {
+        width += c.width().unwrap_or(0);
+        if width + 1 > max_width {
+            break;
+        }
+        fitted.push(c);
+    }
+    fitted.push('…');
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::StringRecord;
+
+    fn table(headers: Option<Vec<&str>>, records: Vec<Vec<&str>>, max_width: usize) -> String {
+        CsvRecord {
+            headers: headers.map(StringRecord::from),
+            records: records.into_iter().map(StringRecord::from).collect(),
+        }
+        .to_table(max_width)
+    }
+
+    #[test]
+    fn test_table_aligns_wide_characters() {
+        let output = table(
+            Some(vec!["Name", "Kit Number"]),
+            vec![vec!["Perin", "37"], vec!["武磊", "7"]],
+            0,
+        );
+        assert_eq!(
+            output,
+            "┌───────┬────────────┐\n\
+             │ Name  │ Kit Number │\n\
+             ├───────┼────────────┤\n\
+             │ Perin │         37 │\n\
+             │ 武磊  │          7 │\n\
+             └───────┴────────────┘"
+        );
+    }
+
+    #[test]
+    fn test_table_truncates_and_keeps_commas() {
+        let output = table(None, vec![vec!["Feb 5, 1985 (34)", "a\nb"]], 8);
+        assert_eq!(
+            output,
+            "┌──────────┬─────┐\n│ Feb 5, … │ a b │\n└──────────┴─────┘"
+        );
+    }
+
+    #[test]
+    fn test_fit_counts_columns_not_chars() {
+        assert_eq!(fit("武磊武磊", 5), "武磊…");
+        assert_eq!(fit("Perin", 5), "Perin");
+    }
+}
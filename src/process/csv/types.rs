@@ -0,0 +1,334 @@
+use super::{nested::Nesting, CsvRecord};
+use crate::CsvType;
+use csv::StringRecord;
+use serde_json::{Number, Value};
+
+/// Type hints for turning cells into JSON values.
+#[derive(Debug, Default, Clone)]
+pub struct ColumnTypes {
+    /// Infer integer, float, boolean and date columns and map empty cells to null.
+    pub infer: bool,
+    /// Forced types keyed by header name, or by zero-based index without a header.
+    pub columns: Vec<(String, CsvType)>,
+    /// Build nested objects and arrays from `address.city` and `tags[0]` style headers.
+    pub nested: bool,
+}
+
+impl CsvType {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            CsvType::String => "string",
+            CsvType::Integer => "integer",
+            CsvType::Float => "float",
+            CsvType::Boolean => "boolean",
+            CsvType::Date => "date",
+        }
+    }
+
+    /// The narrowest type that fits a single non-empty cell.
+    pub(super) fn of(cell: &str) -> Self {
+        if is_integer(cell) {
+            CsvType::Integer
+        } else if is_float(cell) {
+            CsvType::Float
+        } else if parse_bool(cell).is_some() {
+            CsvType::Boolean
+        } else if is_date(cell) {
+            CsvType::Date
+        } else {
+            CsvType::String
+        }
+    }
+
+    /// The narrowest type that fits cells of both types.
+    pub(super) fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (CsvType::Integer, CsvType::Float) | (CsvType::Float, CsvType::Integer) => {
+                CsvType::Float
+            }
+            _ => CsvType::String,
+        }
+    }
+
+    /// Infer a column type from its non-empty cells, falling back to string.
+    pub(super) fn infer<'a>(cells: impl Iterator<Item = &'a str>) -> Self {
+        cells
+            .filter(|cell| !cell.is_empty())
+            .map(CsvType::of)
+            .reduce(CsvType::merge)
+            .unwrap_or(CsvType::String)
+    }
+
+    pub(super) fn convert(self, cell: &str) -> anyhow::Result<Value> {
+        if cell.is_empty() && self != CsvType::String {
+            return Ok(Value::Null);
+        }
+
+        let value = match self {
+            CsvType::String => Value::String(cell.to_string()),
+            CsvType::Integer => Value::Number(
+                cell.parse::<i64>()
+                    .map_err(|_| anyhow::anyhow!("`{}` is not an integer", cell))?
+                    .into(),
+            ),
+            CsvType::Float => cell
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| anyhow::anyhow!("`{}` is not a float", cell))?,
+            CsvType::Boolean => Value::Bool(
+                parse_bool(cell).ok_or_else(|| anyhow::anyhow!("`{}` is not a boolean", cell))?,
+            ),
+            CsvType::Date if is_date(cell) => Value::String(cell.to_string()),
+            CsvType::Date => return Err(anyhow::anyhow!("`{}` is not a YYYY-MM-DD date", cell)),
+        };
+        Ok(value)
+    }
+}
+
+/// Integers with a leading zero, like zip codes or ids, stay strings.
+fn is_integer(cell: &str) -> bool {
+    let digits = cell.strip_prefix(['-', '+']).unwrap_or(cell);
+    cell.parse::<i64>().is_ok() && (digits == "0" || !digits.starts_with('0'))
+}
+
+fn is_float(cell: &str) -> bool {
+    let digits = cell.strip_prefix(['-', '+']).unwrap_or(cell);
+    digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && !(digits.starts_with('0') && digits.as_bytes().get(1).is_some_and(u8::is_ascii_digit))
+        && cell.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+fn parse_bool(cell: &str) -> Option<bool> {
+    match cell.to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn is_date(cell: &str) -> bool {
+    let parts: Vec<_> = cell.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (year.parse::<u32>(), month.parse(), day.parse()) else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// Turns rows into JSON values according to the resolved column types.
+#[derive(Debug, Default)]
+pub(super) struct ValueConverter {
+    types: Vec<Option<CsvType>>,
+    infer_cells: bool,
+    nesting: Option<Nesting>,
+}
+
+impl ValueConverter {
+    /// Resolve types for the whole file, inferring each column from all of its cells.
+    pub(super) fn for_record(types: &ColumnTypes, csv_record: &CsvRecord) -> anyhow::Result<Self> {
+        let mut converter = Self::for_stream(types, csv_record.headers.as_ref())?;
+        if types.infer {
+            let width = csv_record
+                .records
+                .iter()
+                .map(|r| r.len())
+                .max()
+                .unwrap_or(0);
+            converter.types.resize(width, None);
+            for (i, ty) in converter.types.iter_mut().enumerate() {
+                if ty.is_none() {
+                    let cells = csv_record.records.iter().filter_map(|r| r.get(i));
+                    *ty = Some(CsvType::infer(cells));
+                }
+            }
+            converter.infer_cells = false;
+        }
+        Ok(converter)
+    }
+
+    /// Resolve forced column types only; with inference on, every other cell is typed
+    /// on its own since the rest of the column hasn't been read yet.
+    pub(super) fn for_stream(
+        types: &ColumnTypes,
+        headers: Option<&StringRecord>,
+    ) -> anyhow::Result<Self> {
+        let mut resolved = Vec::new();
+        for (column, ty) in &types.columns {
+            let index = column_index(headers, column)?;
+            if resolved.len() <= index {
+                resolved.resize(index + 1, None);
+            }
+            resolved[index] = Some(*ty);
+        }
+        let nesting = match (types.nested, headers) {
+            (true, Some(headers)) => Some(Nesting::new(headers)?),
+            _ => None,
+        };
+        Ok(Self {
+            types: resolved,
+            infer_cells: types.infer,
+            nesting,
+        })
+    }
+
+    /// The type resolved for a column, if any.
+    pub(super) fn column_type(&self, index: usize) -> Option<CsvType> {
+        self.types.get(index).copied().flatten()
+    }
+
+    fn cell(&self, index: usize, cell: &str) -> anyhow::Result<Value> {
+        match self.column_type(index) {
+            Some(ty) => ty.convert(cell),
+            None if self.infer_cells && cell.is_empty() => Ok(Value::Null),
+            None if self.infer_cells => CsvType::of(cell).convert(cell),
+            None => Ok(Value::String(cell.to_string())),
+        }
+    }
+
+    /// Turn a single row into an object keyed by header, or an array when there is no header.
+    pub(super) fn convert(
+        &self,
+        headers: Option<&StringRecord>,
+        record: &StringRecord,
+    ) -> anyhow::Result<Value> {
+        let cells = record.iter().enumerate().map(|(i, cell)| {
+            self.cell(i, cell).map_err(|e| {
+                let column = headers
+                    .and_then(|h| h.get(i))
+                    .map_or_else(|| i.to_string(), str::to_string);
+                let line = record.position().map_or(0, |p| p.line());
+                anyhow::anyhow!("{} in column `{}` on line {}", e, column, line)
+            })
+        });
+        if let (Some(nesting), Some(_)) = (&self.nesting, headers) {
+            let cells = cells.collect::<anyhow::Result<Vec<_>>>()?;
+            return Ok(nesting.nest(cells.into_iter()));
+        }
+        match headers {
+            Some(headers) => Ok(Value::Object(
+                headers
+                    .iter()
+                    .map(str::to_string)
+                    .zip(cells)
+                    .map(|(k, v)| v.map(|v| (k, v)))
+                    .collect::<anyhow::Result<_>>()?,
+            )),
+            None => Ok(Value::Array(cells.collect::<anyhow::Result<_>>()?)),
+        }
+    }
+}
+
+/// One type per column of the whole record, for formats with a fixed schema. Types are
+/// always inferred, forced ones still win, and columns without a type are strings.
+pub(super) fn record_types(
+    csv_record: &CsvRecord,
+    types: &ColumnTypes,
+) -> anyhow::Result<Vec<CsvType>> {
+    let types = ColumnTypes {
+        infer: true,
+        columns: types.columns.clone(),
+        nested: false,
+    };
+    let converter = ValueConverter::for_record(&types, csv_record)?;
+    let width = csv_record
+        .headers
+        .iter()
+        .chain(&csv_record.records)
+        .map(|r| r.len())
+        .max()
+        .unwrap_or(0);
+    Ok((0..width)
+        .map(|i| converter.column_type(i).unwrap_or(CsvType::String))
+        .collect())
+}
+
+pub(super) fn column_index(headers: Option<&StringRecord>, column: &str) -> anyhow::Result<usize> {
+    match headers {
+        Some(headers) => headers
+            .iter()
+            .position(|h| h == column)
+            .ok_or_else(|| anyhow::anyhow!("Unknown column `{}`", column)),
+        None => column.parse().map_err(|_| {
+            anyhow::anyhow!(
+                "Column `{}` must be a zero-based index when there is no header",
+                column
+            )
+        }),
+    }
+}
+
+/// TOML has no null, so null fields are left out of tables and emptied in arrays.
+pub(super) fn drop_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, drop_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| match v {
+                    Value::Null => Value::String(String::new()),
+                    v => drop_nulls(v),
+                })
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_type_of() {
+        assert_eq!(CsvType::of("37"), CsvType::Integer);
+        assert_eq!(CsvType::of("-1.5"), CsvType::Float);
+        assert_eq!(CsvType::of("TRUE"), CsvType::Boolean);
+        assert_eq!(CsvType::of("1990-04-18"), CsvType::Date);
+        assert_eq!(CsvType::of("1990-02-30"), CsvType::String);
+        assert_eq!(CsvType::of("007"), CsvType::String);
+        assert_eq!(CsvType::of("NaN"), CsvType::String);
+    }
+
+    #[test]
+    fn test_csv_type_infer_column() {
+        assert_eq!(CsvType::infer(["1", "", "2.5"].into_iter()), CsvType::Float);
+        assert_eq!(CsvType::infer(["1", "x"].into_iter()), CsvType::String);
+        assert_eq!(CsvType::infer(["", ""].into_iter()), CsvType::String);
+    }
+
+    #[test]
+    fn test_forced_type_error_names_column() {
+        let types = ColumnTypes {
+            infer: false,
+            columns: vec![("kit".to_string(), CsvType::Integer)],
+            nested: false,
+        };
+        let headers = StringRecord::from(vec!["name", "kit"]);
+        let converter = ValueConverter::for_stream(&types, Some(&headers)).unwrap();
+        let err = converter
+            .convert(Some(&headers), &StringRecord::from(vec!["Perin", "x"]))
+            .unwrap_err();
+        assert!(err.to_string().contains("column `kit`"));
+    }
+}
@@ -1,50 +1,192 @@
 use rand::seq::SliceRandom;
+use std::collections::HashSet;
 
-const NUMBERS: &[u8] = b"123456789";
-const UPPERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
-const LOWERS: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
-const SYMBOLS: &[u8] = b"!@#$%^&*-_";
-
-pub fn process_genpass(
-    length: u8,
-    no_upper: bool,
-    no_lower: bool,
-    no_num: bool,
-    no_symbol: bool,
-) -> anyhow::Result<String> {
-    if no_upper && no_lower && no_num && no_symbol {
+const NUMBERS: &str = "0123456789";
+const UPPERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERS: &str = "abcdefghijklmnopqrstuvwxyz";
+const SYMBOLS: &str = "!@#$%^&*-_";
+/// Characters easily misread for one another, left out unless `ambiguous` is set.
+const AMBIGUOUS: &str = "0OIl";
+
+/// The characters a password is drawn from.
+#[derive(Debug, Clone)]
+pub struct GenpassConfig {
+    pub length: u8,
+    pub upper: bool,
+    pub lower: bool,
+    pub number: bool,
+    pub symbol: bool,
+    /// Symbols to use instead of `SYMBOLS`.
+    pub symbols: Option<String>,
+    /// A custom alphabet used instead of all the classes above.
+    pub charset: Option<String>,
+    /// Characters never used, like ones a target system rejects.
+    pub exclude: String,
+    /// Keep the look-alike characters in `AMBIGUOUS`.
+    pub ambiguous: bool,
+}
+
+impl Default for GenpassConfig {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            upper: true,
+            lower: true,
+            number: true,
+            symbol: true,
+            symbols: None,
+            charset: None,
+            exclude: String::new(),
+            ambiguous: false,
+        }
+    }
+}
+
+impl GenpassConfig {
+    /// The enabled character classes with their names, after the exclusions.
+    fn classes(&self) -> anyhow::Result<Vec<(&'static str, Vec<char>)>> {
+        let classes = match &self.charset {
+            Some(charset) => vec![("--charset", charset.as_str(), true)],
+            None => vec![
+                ("uppercase letters", UPPERS, self.upper),
+                ("lowercase letters", LOWERS, self.lower),
+                ("numbers", NUMBERS, self.number),
+                (
+                    "symbols",
+                    self.symbols.as_deref().unwrap_or(SYMBOLS),
+                    self.symbol,
+                ),
+            ],
+        };
+        // a custom charset is taken as given, only --exclude applies to it
+        let drop_ambiguous = !self.ambiguous && self.charset.is_none();
+        let mut enabled = Vec::new();
+        for (name, chars, _) in classes.into_iter().filter(|(_, _, on)| *on) {
+            let mut class: Vec<char> = Vec::new();
+            for c in chars.chars() {
+                let ambiguous = drop_ambiguous && AMBIGUOUS.contains(c);
+                if !ambiguous && !self.exclude.contains(c) && !class.contains(&c) {
+                    class.push(c);
+                }
+            }
+            if class.is_empty() {
+                return Err(anyhow::anyhow!("No {} left to use after --exclude", name));
+            }
+            enabled.push((name, class));
+        }
+        Ok(enabled)
+    }
+}
+
+/// A random password with at least one character of every enabled class.
+pub fn process_genpass(config: &GenpassConfig) -> anyhow::Result<String> {
+    let classes = config.classes()?;
+    if classes.is_empty() {
         return Err(anyhow::anyhow!(
-            "At least one of no_upper, no_lower, no_num, no_symbol must be false"
+            "At least one of upper, lower, number or symbol characters has to be enabled"
+        ));
+    }
+    if (config.length as usize) < classes.len() {
+        return Err(anyhow::anyhow!(
+            "A length of {} can't hold a character of each of the {} enabled classes",
+            config.length,
+            classes.len()
         ));
     }
 
     let mut rng = rand::thread_rng();
     let mut chars = Vec::new();
     let mut password = Vec::new();
-
-    if !no_upper {
-        chars.extend_from_slice(UPPERS);
-        password.push(*UPPERS.choose(&mut rng).expect("UPPERS won't be empty"));
+    for (_, class) in &classes {
+        chars.extend_from_slice(class);
+        password.push(*class.choose(&mut rng).expect("classes won't be empty"));
     }
-    if !no_lower {
-        chars.extend_from_slice(LOWERS);
-        password.push(*LOWERS.choose(&mut rng).expect("UPPERS won't be empty"));
+
+    for _ in 0..config.length as usize - password.len() {
+        password.push(*chars.choose(&mut rng).expect("chars won't be empty"));
     }
-    if !no_num {
-        chars.extend_from_slice(NUMBERS);
-        password.push(*NUMBERS.choose(&mut rng).expect("NUMBERS won't be empty"));
+
+    password.shuffle(&mut rng);
+    Ok(password.into_iter().collect())
+}
+
+/// The 2048-word BIP39 English list, the built-in passphrase wordlist.
+const BIP39_WORDS: &str = include_str!("../../assets/bip39_english.txt");
+
+/// How a diceware-style passphrase is put together.
+#[derive(Debug, Clone)]
+pub struct PassphraseConfig {
+    pub words: u8,
+    pub separator: String,
+    /// Start every word with a capital letter.
+    pub capitalize: bool,
+    /// `bip39` for the built-in list, or a file with one word per line.
+    pub wordlist: String,
+}
+
+impl Default for PassphraseConfig {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            wordlist: "bip39".to_string(),
+        }
     }
-    if !no_symbol {
-        chars.extend_from_slice(SYMBOLS);
-        password.push(*SYMBOLS.choose(&mut rng).expect("SYMBOLS won't be empty"));
+}
+
+/// The words of a wordlist, deduplicated. Files can be plain lists or diceware lists
+/// like EFF's, where every line starts with the dice roll, `11111 abacus`.
+fn wordlist(name: &str) -> anyhow::Result<Vec<String>> {
+    let contents = match name {
+        "bip39" => BIP39_WORDS.to_string(),
+        "eff-large" | "eff-short" => {
+            return Err(anyhow::anyhow!(
+                "The EFF wordlists aren't built in, download one from \
+                 https://www.eff.org/dice and pass its path to --wordlist"
+            ))
+        }
+        path => std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Can't read wordlist {}: {}", path, e))?,
+    };
+    let mut seen = HashSet::new();
+    let words: Vec<String> = contents
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .filter(|word| seen.insert(*word))
+        .map(str::to_string)
+        .collect();
+    if words.len() < 2 {
+        return Err(anyhow::anyhow!("Wordlist {} needs at least 2 words", name));
     }
+    Ok(words)
+}
 
-    for _ in 0..length - password.len() as u8 {
-        password.push(*chars.choose(&mut rng).expect("chars won't be empty"));
+/// A passphrase of words picked uniformly at random from the wordlist.
+pub fn process_passphrase(config: &PassphraseConfig) -> anyhow::Result<String> {
+    if config.words == 0 {
+        return Err(anyhow::anyhow!("A passphrase needs at least one word"));
     }
+    let words = wordlist(&config.wordlist)?;
+    let mut rng = rand::thread_rng();
+    let passphrase: Vec<String> = (0..config.words)
+        .map(|_| {
+            let word = words.choose(&mut rng).expect("wordlist won't be empty");
+            match config.capitalize {
+                true => capitalize(word),
+                false => word.clone(),
+            }
+        })
+        .collect();
+    Ok(passphrase.join(&config.separator))
+}
 
-    password.shuffle(&mut rng);
-    Ok(String::from_utf8(password)?)
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -53,7 +195,7 @@ mod tests {
 
     #[test]
     fn test_process_genpass() {
-        let password = process_genpass(16, false, false, false, false).unwrap();
+        let password = process_genpass(&GenpassConfig::default()).unwrap();
         assert_eq!(password.len(), 16);
         assert!(
             has_upper_char(&password)
@@ -65,7 +207,11 @@ mod tests {
 
     #[test]
     fn test_process_genpass_no_upper() {
-        let password = process_genpass(16, true, false, false, false).unwrap();
+        let password = process_genpass(&GenpassConfig {
+            upper: false,
+            ..Default::default()
+        })
+        .unwrap();
         assert_eq!(password.len(), 16);
         assert!(!has_upper_char(&password));
         assert!(has_lower_char(&password) && has_num_char(&password) && has_symbol_char(&password));
@@ -73,7 +219,11 @@ mod tests {
 
     #[test]
     fn test_process_genpass_no_lower() {
-        let password = process_genpass(16, false, true, false, false).unwrap();
+        let password = process_genpass(&GenpassConfig {
+            lower: false,
+            ..Default::default()
+        })
+        .unwrap();
         assert_eq!(password.len(), 16);
         assert!(!has_lower_char(&password));
         assert!(has_upper_char(&password) && has_num_char(&password) && has_symbol_char(&password));
@@ -81,7 +231,11 @@ mod tests {
 
     #[test]
     fn test_process_genpass_no_num() {
-        let password = process_genpass(16, false, false, true, false).unwrap();
+        let password = process_genpass(&GenpassConfig {
+            number: false,
+            ..Default::default()
+        })
+        .unwrap();
         assert_eq!(password.len(), 16);
         assert!(!has_num_char(&password));
         assert!(
@@ -91,12 +245,87 @@ mod tests {
 
     #[test]
     fn test_process_genpass_no_symbol() {
-        let password = process_genpass(16, false, false, false, true).unwrap();
+        let password = process_genpass(&GenpassConfig {
+            symbol: false,
+            ..Default::default()
+        })
+        .unwrap();
         assert_eq!(password.len(), 16);
         assert!(!has_symbol_char(&password));
         assert!(has_upper_char(&password) && has_lower_char(&password) && has_num_char(&password));
     }
 
+    #[test]
+    fn test_process_genpass_ambiguous_and_exclude() {
+        let strict = GenpassConfig {
+            length: 200,
+            ..Default::default()
+        };
+        let password = process_genpass(&strict).unwrap();
+        assert!(!password.contains(|c| AMBIGUOUS.contains(c)));
+        let password = process_genpass(&GenpassConfig {
+            ambiguous: true,
+            exclude: "abc".to_string(),
+            ..strict.clone()
+        })
+        .unwrap();
+        assert!(password.contains(|c| AMBIGUOUS.contains(c)));
+        assert!(!password.contains(['a', 'b', 'c']));
+
+        let no_numbers = GenpassConfig {
+            exclude: "123456789".to_string(),
+            ..Default::default()
+        };
+        assert!(process_genpass(&no_numbers).is_err());
+        let too_short = GenpassConfig {
+            length: 3,
+            ..Default::default()
+        };
+        assert!(process_genpass(&too_short).is_err());
+    }
+
+    #[test]
+    fn test_process_genpass_charset_and_symbols() {
+        let password = process_genpass(&GenpassConfig {
+            charset: Some("0123456789abcdef".to_string()),
+            exclude: "f".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(password.len(), 16);
+        assert!(password.chars().all(|c| "0123456789abcde".contains(c)));
+
+        let password = process_genpass(&GenpassConfig {
+            symbols: Some("+=".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(password.contains(['+', '=']) && !has_symbol_char(&password));
+    }
+
+    #[test]
+    fn test_process_passphrase() {
+        let passphrase = process_passphrase(&PassphraseConfig::default()).unwrap();
+        let words: Vec<_> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|w| BIP39_WORDS.lines().any(|l| l == *w)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dice.txt");
+        std::fs::write(&path, "11111\tabacus\n11112\tabdomen\n\n").unwrap();
+        let passphrase = process_passphrase(&PassphraseConfig {
+            words: 4,
+            separator: " ".to_string(),
+            capitalize: true,
+            wordlist: path.to_str().unwrap().to_string(),
+        })
+        .unwrap();
+        assert!(passphrase
+            .split(' ')
+            .all(|w| w == "Abacus" || w == "Abdomen"));
+        assert_eq!(passphrase.split(' ').count(), 4);
+    }
+
     fn has_upper_char(input: &str) -> bool {
         input.chars().any(|c| c.is_uppercase())
     }
@@ -110,6 +339,6 @@ mod tests {
     }
 
     fn has_symbol_char(input: &str) -> bool {
-        input.chars().any(|c| SYMBOLS.contains(&(c as u8)))
+        input.chars().any(|c| SYMBOLS.contains(c))
     }
 }
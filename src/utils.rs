@@ -1,4 +1,9 @@
-use std::{fmt::Write, fs, io::Read, path::Path};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
 
 use anyhow::{Error, Result};
 
@@ -12,11 +17,13 @@ pub fn verify_input(s: &str) -> Result<String> {
     }
 }
 
-pub fn output_contents(output: &str, contents: &str) {
+pub fn output_contents(output: &str, contents: &str) -> Result<()> {
     if output != "-" {
-        fs::write(output, contents).unwrap();
+        fs::write(output, contents)?;
+        Ok(())
     } else {
         println!("{}", contents);
+        Ok(())
     }
 }
 
@@ -28,6 +35,128 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     }
 }
 
+/// Like `get_reader`, but gzip, zstd, bzip2 and xz input is decompressed on the fly.
+/// The compression is told by the magic bytes, so it works for stdin too.
+pub fn get_decompressed_reader(input: &str) -> Result<Box<dyn Read>> {
+    let mut reader = get_reader(input)?;
+    let mut magic = Vec::with_capacity(6);
+    (&mut reader).take(6).read_to_end(&mut magic)?;
+    let reader = io::Cursor::new(magic.clone()).chain(reader);
+    Ok(match Compression::from_magic(&magic) {
+        Some(Compression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(reader)?),
+        Some(Compression::Bzip2) => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Some(Compression::Xz) => Box::new(liblzma::read::XzDecoder::new_multi_decoder(reader)),
+        None => Box::new(reader),
+    })
+}
+
+/// A buffered writer to stdout or a file, compressed when the file name ends in `.gz`,
+/// `.zst`, `.bz2` or `.xz`. Call `OutputWriter::finish` once everything is written.
+pub fn get_writer(output: &str) -> Result<OutputWriter> {
+    if output == "-" {
+        return Ok(OutputWriter::Stdout(BufWriter::new(std::io::stdout())));
+    }
+    let file = fs::File::create(output)?;
+    Ok(match Compression::from_path(output) {
+        Some(Compression::Gzip) => OutputWriter::Gzip(BufWriter::new(
+            flate2::write::GzEncoder::new(file, flate2::Compression::default()),
+        )),
+        Some(Compression::Zstd) => OutputWriter::Zstd(BufWriter::new(zstd::Encoder::new(
+            file,
+            zstd::DEFAULT_COMPRESSION_LEVEL,
+        )?)),
+        Some(Compression::Bzip2) => OutputWriter::Bzip2(BufWriter::new(
+            bzip2::write::BzEncoder::new(file, bzip2::Compression::default()),
+        )),
+        Some(Compression::Xz) => {
+            OutputWriter::Xz(BufWriter::new(liblzma::write::XzEncoder::new(file, 6)))
+        }
+        None => OutputWriter::File(BufWriter::new(file)),
+    })
+}
+
+/// The writer `get_writer` returns.
+pub enum OutputWriter {
+    Stdout(BufWriter<io::Stdout>),
+    File(BufWriter<fs::File>),
+    Gzip(BufWriter<flate2::write::GzEncoder<fs::File>>),
+    Zstd(BufWriter<zstd::Encoder<'static, fs::File>>),
+    Bzip2(BufWriter<bzip2::write::BzEncoder<fs::File>>),
+    Xz(BufWriter<liblzma::write::XzEncoder<fs::File>>),
+}
+
+impl OutputWriter {
+    /// Flush and end the compressed stream. Errors on the last write show up here, where
+    /// dropping the writer would lose them and leave a truncated file behind.
+    pub fn finish(self) -> Result<()> {
+        fn unbuffer<W: Write>(writer: BufWriter<W>) -> io::Result<W> {
+            writer.into_inner().map_err(|e| e.into_error())
+        }
+        let mut file = match self {
+            Self::Stdout(mut writer) => return Ok(writer.flush()?),
+            Self::File(writer) => unbuffer(writer)?,
+            Self::Gzip(writer) => unbuffer(writer)?.finish()?,
+            Self::Zstd(writer) => unbuffer(writer)?.finish()?,
+            Self::Bzip2(writer) => unbuffer(writer)?.finish()?,
+            Self::Xz(writer) => unbuffer(writer)?.finish()?,
+        };
+        file.flush()?;
+        Ok(())
+    }
+
+    fn inner(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Stdout(writer) => writer,
+            Self::File(writer) => writer,
+            Self::Gzip(writer) => writer,
+            Self::Zstd(writer) => writer,
+            Self::Bzip2(writer) => writer,
+            Self::Xz(writer) => writer,
+        }
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner().flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    fn from_magic(magic: &[u8]) -> Option<Self> {
+        match magic {
+            [0x1f, 0x8b, ..] => Some(Self::Gzip),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Self::Zstd),
+            [b'B', b'Z', b'h', ..] => Some(Self::Bzip2),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00] => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            "bz2" => Some(Self::Bzip2),
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+}
+
 pub fn get_content(input: &str) -> Result<String> {
     if Path::new(input).exists() {
         Ok(fs::read_to_string(input)?)